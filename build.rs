@@ -0,0 +1,37 @@
+use std::{path::Path, process::Command};
+
+//Хэш коммита для манифеста расчета (unknown, если сборка идет не из git-репозитория)
+fn git_hash() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+//При включенной опции capi-header заголовок include/mmuvp.h перегенерируется по src/ffi.rs
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    for path in [".git/HEAD", ".git/refs/heads", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    println!("cargo:rustc-env=MMUVP_GIT_HASH={}", git_hash());
+    #[cfg(feature = "capi-header")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+            .expect("Ошибка чтения cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_crate(&crate_dir)
+            .with_config(config)
+            .generate()
+            .expect("Ошибка генерации заголовка mmuvp.h")
+            .write_to_file(format!("{}/include/mmuvp.h", crate_dir));
+    }
+}
@@ -0,0 +1,99 @@
+/*
+ * Проверка C интерфейса: создание материальной точки, шаги одноосного растяжения,
+ * касательный модуль, сериализация и восстановление состояния.
+ *
+ * Сборка и запуск из корня репозитория:
+ *   cargo build --release
+ *   cc capi/test_driver.c -Iinclude -Ltarget/release -lmmuvp_ecs_2_0 -lm -o target/test_driver
+ *   LD_LIBRARY_PATH=target/release ./target/test_driver input/param.json
+ */
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "mmuvp.h"
+
+static char *read_file(const char *path) {
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+        return NULL;
+    }
+    fseek(file, 0, SEEK_END);
+    long size = ftell(file);
+    fseek(file, 0, SEEK_SET);
+    char *data = malloc((size_t)size + 1);
+    if (data != NULL && fread(data, 1, (size_t)size, file) == (size_t)size) {
+        data[size] = '\0';
+    } else {
+        free(data);
+        data = NULL;
+    }
+    fclose(file);
+    return data;
+}
+
+int main(int argc, char **argv) {
+    const char *param_path = argc > 1 ? argv[1] : "input/param.json";
+    char *params = read_file(param_path);
+    if (params == NULL) {
+        fprintf(stderr, "cannot read %s\n", param_path);
+        return 1;
+    }
+
+    MmuvpState *state = mmuvp_create(params);
+    free(params);
+    if (state == NULL) {
+        fprintf(stderr, "mmuvp_create failed\n");
+        return 1;
+    }
+
+    const double dt = 1.0e-3;
+    const double rate = 1.0e-2;
+    const double grad_v[9] = {rate, 0.0, 0.0, 0.0, -rate / 2.0, 0.0, 0.0, 0.0, -rate / 2.0};
+    double stress[6];
+    for (int step = 0; step < 100; step++) {
+        if (mmuvp_step_grad_v(state, grad_v, dt, stress) != MMUVP_OK) {
+            fprintf(stderr, "mmuvp_step_grad_v failed at step %d\n", step);
+            return 1;
+        }
+    }
+    printf("stress after 100 steps: %g %g %g\n", stress[0], stress[1], stress[2]);
+
+    const double dstran[6] = {rate * dt, -rate * dt / 2.0, -rate * dt / 2.0, 0.0, 0.0, 0.0};
+    double ddsdde[36];
+    if (mmuvp_step(state, dstran, dt, stress, ddsdde) != MMUVP_OK) {
+        fprintf(stderr, "mmuvp_step failed\n");
+        return 1;
+    }
+    printf("tangent C11 C12 C44: %g %g %g\n", ddsdde[0], ddsdde[1], ddsdde[21]);
+    if (ddsdde[0] <= 0.0) {
+        fprintf(stderr, "non-positive tangent\n");
+        return 1;
+    }
+
+    char *saved = mmuvp_serialize(state);
+    MmuvpState *restored = mmuvp_deserialize(saved);
+    mmuvp_string_free(saved);
+    if (restored == NULL) {
+        fprintf(stderr, "mmuvp_deserialize failed\n");
+        return 1;
+    }
+
+    double restored_stress[6];
+    mmuvp_get_stress(restored, restored_stress);
+    if (memcmp(stress, restored_stress, sizeof(stress)) != 0) {
+        fprintf(stderr, "restored stress differs\n");
+        return 1;
+    }
+    int32_t grains = 0;
+    if (mmuvp_grain_count(restored, &grains) != MMUVP_OK) {
+        fprintf(stderr, "mmuvp_grain_count failed\n");
+        return 1;
+    }
+    printf("grains: %d, restored state OK\n", (int)grains);
+
+    mmuvp_free(restored);
+    mmuvp_free(state);
+    return 0;
+}
@@ -0,0 +1,57 @@
+/*
+ * C интерфейс материальной точки MMUVP.
+ * Тензоры передаются построчно (9 значений), векторы Фойгта в порядке 11, 22, 33, 12, 13, 23.
+ * Напряжения и касательный модуль в МПа, сдвиговые деформации в dstran инженерные.
+ */
+
+#ifndef MMUVP_H
+#define MMUVP_H
+
+/* Файл сгенерирован cbindgen (cargo build --features capi-header). Не редактировать вручную. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MMUVP_OK 0
+
+#define MMUVP_ERROR_NULL_POINTER -1
+
+#define MMUVP_ERROR_INVALID_INPUT -2
+
+#define MMUVP_ERROR_PANIC -3
+
+typedef struct MmuvpState MmuvpState;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct MmuvpState *mmuvp_create(const char *params_json);
+
+int mmuvp_step_grad_v(struct MmuvpState *state, const double *grad_v, double dt, double *stress);
+
+int mmuvp_step(struct MmuvpState *state,
+               const double *dstran,
+               double dt,
+               double *stress,
+               double *ddsdde);
+
+int mmuvp_get_stress(const struct MmuvpState *state, double *stress);
+
+int mmuvp_grain_count(const struct MmuvpState *state, int32_t *count);
+
+char *mmuvp_serialize(const struct MmuvpState *state);
+
+struct MmuvpState *mmuvp_deserialize(const char *data);
+
+void mmuvp_string_free(char *data);
+
+void mmuvp_free(struct MmuvpState *state);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* MMUVP_H */
@@ -1,11 +1,25 @@
 #![allow(dead_code)]
 #![allow(unused_macros)]
 
-use std::{fs, time::Duration};
-
-use crate::mmuvp::elasticity::components::{SigmaComponent, EpsComponent};
-
-use super::consts::FILE_OUTPUT_PATH;
+use std::{fs, io, path::Path, time::Duration};
+
+use nalgebra::Matrix3;
+
+use crate::{
+    consts::{OUTPUT_FILES, SNAPSHOT_DIR, TIME_SERIES_FILES},
+    mmuvp::{
+        elasticity::{
+            components::{EpsComponent, SigmaComponent},
+            systems::{calc_intensity_eps, calc_intensity_s},
+        },
+        params::{
+            components::{NucleusOrientationModel, Params, StageKind, StoredEnergyMode},
+            systems::get_total_steps,
+        },
+        recrystallization::systems::calc_zener_pressure,
+        standart_deformation::uniaxial_tension,
+    },
+};
 
 #[macro_export]
 macro_rules! create_component_map {
@@ -16,79 +30,196 @@ macro_rules! create_component_map {
 
 #[macro_export]
 macro_rules! insert_component {
-    ($entity:expr, $component:expr, $map:ident) => {
+    ($entity:expr, $component:expr, $map:expr) => {
         $map.insert($entity.clone(), $component);
     };
 }
 
-pub fn clear_output_folder(){
-    // Получаем список файлов и директорий внутри указанной папки
-    let entries = fs::read_dir(FILE_OUTPUT_PATH).expect("Ошибка открытия дирректории вывода");
-
-    for entry in entries {
-        let entry = entry.expect("Ошибка проверки наличия файла");
-        let path = entry.path();
+#[macro_export]
+macro_rules! remove_component {
+    ($entity:expr, $map:expr) => {
+        $map.remove($entity);
+    };
+}
 
-        // Проверяем, является ли элемент файлом
+//Создает папку вывода (вместе с родительскими) и удаляет из нее результаты прошлого расчета.
+//Удаляются только файлы из списка OUTPUT_FILES в самой папке вывода и снимки grains_* в папке
+//снимков, остальные файлы не трогаются.
+pub fn prepare_output_folder(output_path: &Path) -> io::Result<()> {
+    fs::create_dir_all(output_path)?;
+    for file_name in OUTPUT_FILES.iter() {
+        let path = output_path.join(file_name);
         if path.is_file() {
-            // Удаляем файл
-            fs::remove_file(&path).expect("Ошибка удаления файла");
+            fs::remove_file(&path)?;
+        }
+    }
+
+    let snapshot_path = output_path.join(SNAPSHOT_DIR);
+    if snapshot_path.is_dir() {
+        for entry in fs::read_dir(&snapshot_path)? {
+            let path = entry?.path();
+            let is_snapshot = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    (name.starts_with("grains_") && (name.ends_with(".csv") || name.ends_with(".npy")))
+                        || (name.starts_with("grain_size_") && name.ends_with(".dat"))
+                });
+            if path.is_file() && is_snapshot {
+                fs::remove_file(&path)?;
+            }
         }
     }
+    Ok(())
 }
 
-pub fn time_remaining(current_step: u64, current_time: Duration, last_step: u64) -> String {
-    if current_step >= last_step {
-        // Если текущий шаг больше или равен последнему, цикл завершился.
-        return "0 минут 0 секунд".to_string()
+//Удаляет из временных рядов строки со временем не меньше time_limit. Используется при продолжении
+//расчета с контрольной точки, чтобы результаты совпадали с непрерывным расчетом
+pub fn trim_output_files(output_path: &Path, time_limit: f64) -> io::Result<()> {
+    for (file_name, time_column) in TIME_SERIES_FILES.iter() {
+        let path = output_path.join(file_name);
+        if !path.is_file() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let mut trimmed = String::with_capacity(content.len());
+        for line in content.lines() {
+            let time = line
+                .split_whitespace()
+                .nth(*time_column)
+                .and_then(|value| value.parse::<f64>().ok());
+            if line.starts_with('#') || time.is_some_and(|time| time < time_limit) {
+                trimmed.push_str(line);
+                trimmed.push('\n');
+            }
+        }
+        fs::write(&path, trimmed)?;
     }
-  
-    let estimated_duration_per_step = current_time.as_secs_f64() / (current_step as f64);
-    // Оценка оставшегося времени на основе времени, прошедшего с начала цикла.
-    let remaining_steps = (last_step - current_step - 1) as f64;
-    let remaining_time = (estimated_duration_per_step * remaining_steps) as u64;
+    Ok(())
+}
 
-    let minutes = remaining_time / 60;
-    let seconds = remaining_time % 60;
+//Оценка оставшегося времени расчета (с) по среднему времени шага
+pub fn time_remaining(current_step: u64, current_time: Duration, last_step: u64) -> f64 {
+    if current_step == 0 || current_step >= last_step {
+        return 0.0;
+    }
+    current_time.as_secs_f64() / current_step as f64 * (last_step - current_step) as f64
+}
 
-    format!("{} минут {} секунд", minutes, seconds)
+//Строка журнала о ходе расчета в формате key=value (одна строка на вывод, удобно для grep/awk).
+//Компоненты тензоров в лабораторной системе координат в порядке 11, 22, 33, 12, 13, 23
+pub fn print_current_sys(
+    current_time: Duration,
+    step: i64,
+    last_step: i64,
+    polycrystal_eps: &EpsComponent,
+    polycrystal_sigma: &SigmaComponent,
+    grain_count: usize,
+) {
+    let voigt = |tensor: Matrix3<f64>| {
+        [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)]
+            .map(|(i, j)| format!("{:.4e}", tensor[(i, j)]))
+            .join(",")
+    };
+    println!(
+        "level=info event=progress step={} last_step={} elapsed_s={:.1} remaining_s={:.1} grains={} \
+         eps_i={:.4e} sigma_i={:.4e} eps={} sigma={}",
+        step,
+        last_step,
+        current_time.as_secs_f64(),
+        time_remaining(step as u64, current_time, last_step as u64),
+        grain_count,
+        calc_intensity_eps(polycrystal_eps),
+        calc_intensity_s(polycrystal_sigma),
+        voigt(polycrystal_eps.get_tensor()),
+        voigt(polycrystal_sigma.get_tensor()),
+    );
 }
 
-pub fn print_current_sys(current_time: Duration, step: i64, last_step:i64, polycrystal_eps: &EpsComponent, polycrystal_sigma: &SigmaComponent){
-    let mut sigma = String::new();
-    for (i, &x) in polycrystal_sigma.get_tensor().iter().enumerate() {
-        let formatted = format!("{:.2}", x);
-        sigma.push_str(&formatted);
-        if i % 3 == 2 {
-            sigma.push('\n');
+//Описание используемой модели и итоговых значений параметров (команда describe)
+pub fn describe_model(params: &Params) -> String {
+    let grad_v = uniaxial_tension(params.loading.strain_rate);
+    let mut text = String::new();
+    text.push_str("Модель:\n");
+    text.push_str(&format!(" Поликристалл из {} зерен ГЦК, 24 системы скольжения {{111}}<110>\n", params.polycrystal.grain_num));
+    text.push_str(" Вязкопластический закон скольжения, начальные критические напряжения с поправкой Холла-Петча\n");
+    text.push_str(" Упрочнение: закон с насыщением, латентное упрочнение\n");
+    text.push_str(match params.recrystallization.stored_energy {
+        StoredEnergyMode::Mean => " Рекристаллизация: зарождение новых зерен из субзерен по средней запасенной энергии поликристалла\n",
+        StoredEnergyMode::Grain => " Рекристаллизация: зарождение новых зерен из субзерен по запасенной энергии каждого зерна\n",
+    });
+    let orientation = &params.recrystallization.nucleus_orientation;
+    let model = match orientation.model {
+        NucleusOrientationModel::Random => "",
+        NucleusOrientationModel::Inherit => "ориентация исходного зерна",
+        NucleusOrientationModel::Cube => "кубическая {100}<001>",
+        NucleusOrientationModel::Twin => "двойник Σ3 исходного зерна",
+    };
+    if model.is_empty() {
+        text.push_str(" Ориентация зародышей: случайная\n");
+    } else {
+        text.push_str(&format!(
+            " Ориентация зародышей: {} (доля {}, рассеяние {:.2}°), остальные случайные\n",
+            model,
+            orientation.fraction,
+            orientation.scatter.to_degrees()
+        ));
+    }
+    if params.recrystallization.grain_growth {
+        text.push_str(" Миграция границ: рост рекристаллизованных зерен с сохранением объема, пересчет Холла-Петча\n");
+    }
+    if params.pinning.fraction > 0.0 || params.pinning.initial_fraction > 0.0 {
+        let pressure = |fraction: f64| calc_zener_pressure(fraction, params.recrystallization.egb, params.pinning.radius);
+        if params.pinning.precipitation_time > 0.0 {
+            text.push_str(&format!(
+                " Закрепление границ частицами: давление Зинера от {:.4e} до {:.4e} МПа (выделение частиц за {:e} с)\n",
+                pressure(params.pinning.initial_fraction),
+                pressure(params.pinning.fraction),
+                params.pinning.precipitation_time
+            ));
         } else {
-            sigma.push(' ');
+            text.push_str(&format!(" Закрепление границ частицами: давление Зинера {:.4e} МПа\n", pressure(params.pinning.fraction)));
         }
     }
-
-    let mut eps = String::new();
-    for (i, &x) in polycrystal_eps.get_tensor().iter().enumerate() {
-        let formatted = format!("{:.2}", x);
-        eps.push_str(&formatted);
-        if i % 3 == 2 {
-            eps.push('\n');
-        } else {
-            eps.push(' ');
+    if params.subgrains.m0 > 0.0 {
+        text.push_str(" Субзерна: укрупнение за счет миграции малоугловых границ\n");
+    }
+    if params.recovery.rate0 > 0.0 || params.recovery.dynamic > 0.0 {
+        text.push_str(" Возврат: статический (термоактивируемый) и динамический, новые зерна без упрочнения\n");
+    }
+    if params.stages.is_empty() {
+        text.push_str(&format!(
+            " Шаг по времени {:e} с, число шагов {}, полное время {:e} с\n",
+            params.time.dt,
+            params.time.steps_num,
+            params.time.dt * params.time.steps_num as f64
+        ));
+        text.push_str(" Нагружение: одноосное растяжение, градиент скорости, 1/с:\n");
+        for i in 0..3 {
+            text.push_str(&format!("  {:>12.4e} {:>12.4e} {:>12.4e}\n", grad_v[(i, 0)], grad_v[(i, 1)], grad_v[(i, 2)]));
+        }
+    } else {
+        text.push_str(&format!(" Стадии процесса ({}, всего шагов {}):\n", params.stages.len(), get_total_steps(params)));
+        for (index, stage) in params.stages.iter().enumerate() {
+            let kind = match stage.kind {
+                StageKind::Deformation => format!("одноосное растяжение {:e} 1/с", stage.strain_rate),
+                StageKind::Anneal if stage.relax_stress => "выдержка с релаксацией напряжений".to_string(),
+                StageKind::Anneal => "выдержка без напряжений".to_string(),
+            };
+            text.push_str(&format!(
+                "  {}. {}: {}, {:e} с, шаг {:e} с ({} шагов), {} K\n",
+                index + 1,
+                stage.name,
+                kind,
+                stage.duration,
+                stage.dt,
+                stage.get_steps(),
+                stage.temp
+            ));
         }
     }
-
-    let current_step = step as u64;
-    let last_step = last_step as u64;
-    let curr_mins = current_time.as_secs() / 60;
-    let curr_secs = current_time.as_secs() % 60;
-    let string_time = format!("{} мин {} сек", curr_mins, curr_secs);
-    let string_step = format!("{}/{}", current_step, last_step);
-
-    let string_time_remaining:String = time_remaining(current_step, current_time, last_step);
-
-    print!(
-"****************************************************************************************\n
- Текущее время: {}. Текущий шаг: {}. Осталось времени: {}\n
- Напряжения:\n{} Деформации:\n{}\n
-****************************************************************************************\n", string_time, string_step, string_time_remaining, sigma, eps);
-}
\ No newline at end of file
+    text.push_str("Параметры (СИ):\n");
+    text.push_str(&serde_json::to_string_pretty(params).expect("Ошибка сериализации параметров"));
+    text.push('\n');
+    text
+}
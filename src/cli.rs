@@ -0,0 +1,102 @@
+//Аргументы командной строки исполняемого файла
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+
+use mmuvp_ecs_2_0::{
+    consts::{FILE_INPUT_PATH, FILE_OUTPUT_PATH},
+    mmuvp::params::{
+        components::{ConfigError, Params},
+        systems::{from_value, insert_value, read_json},
+    },
+};
+
+#[derive(Parser)]
+#[command(version, about = "Многоуровневая модель неупругого деформирования и рекристаллизации поликристаллов")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Файл параметров модели
+    #[arg(short, long, global = true, default_value_os_t = PathBuf::from(FILE_INPUT_PATH).join("param.json"))]
+    pub config: PathBuf,
+
+    /// Папка для файлов результатов
+    #[arg(short, long, global = true, default_value = FILE_OUTPUT_PATH)]
+    pub output: PathBuf,
+
+    /// Изменение параметра, например --set slip.tau_c="70 MPa" (можно указывать несколько раз)
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    pub overrides: Vec<String>,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Расчет (по умолчанию)
+    Run {
+        /// Продолжить расчет с контрольной точки (параметры берутся из нее, --set применяется поверх)
+        #[arg(long, value_name = "CHECKPOINT")]
+        restart: Option<PathBuf>,
+    },
+    /// Проверка файла параметров без запуска расчета
+    ValidateConfig,
+    /// Вывод используемой модели и итоговых значений параметров
+    Describe,
+    /// Параметрическое исследование: параллельные расчеты в подпапках папки вывода
+    Sweep {
+        /// Файл исследования с сеткой ("grid") или списком ("cases") значений параметров
+        file: PathBuf,
+        /// Число параллельных расчетов (по умолчанию по числу ядер)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+}
+
+impl Cli {
+    pub fn get_command(&self) -> Command {
+        self.command.clone().unwrap_or(Command::Run { restart: None })
+    }
+
+    //Параметры из файла с изменениями из командной строки. Ошибки файла и всех изменений
+    //собираются в один отчет.
+    pub fn load_params(&self) -> Result<Params, ConfigError> {
+        self.apply_overrides(read_json(&self.config)?)
+    }
+
+    //Параметры контрольной точки с изменениями из командной строки (например, число шагов)
+    pub fn override_params(&self, params: &Params) -> Result<Params, ConfigError> {
+        self.apply_overrides(serde_json::to_value(params).expect("Ошибка сериализации параметров"))
+    }
+
+    fn apply_overrides(&self, mut value: Value) -> Result<Params, ConfigError> {
+        let mut problems = Vec::new();
+        for item in self.overrides.iter() {
+            match parse_override(item) {
+                Ok((key, new_value)) => {
+                    if let Err(err) = insert_value(&mut value, key, new_value) {
+                        problems.push(err);
+                    }
+                }
+                Err(err) => problems.push(err),
+            }
+        }
+        match from_value(&value) {
+            Ok(params) if problems.is_empty() => Ok(params),
+            Ok(_) => Err(ConfigError::new(problems)),
+            Err(err) => {
+                problems.extend_from_slice(err.get_problems());
+                Err(ConfigError::new(problems))
+            }
+        }
+    }
+}
+
+//Значение "key=value": число или строка JSON, иначе строка как есть ("70 MPa")
+fn parse_override(item: &str) -> Result<(&str, Value), String> {
+    let (key, text) = item
+        .split_once('=')
+        .ok_or_else(|| format!("изменение параметра \"{}\" должно иметь вид KEY=VALUE", item))?;
+    let value = serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+    Ok((key.trim(), value))
+}
@@ -1,11 +1,38 @@
 #![allow(dead_code)]
-//Файловая система
-pub const FILE_OUTPUT_PATH: &str = "output\\";
-pub const FILE_INPUT_PATH: &str = "input\\";
+//Файловая система. Папки по умолчанию относительно рабочей директории,
+//пути собираются через Path::join, поэтому разделитель зависит от платформы
+pub const FILE_OUTPUT_PATH: &str = "output";
+pub const FILE_INPUT_PATH: &str = "input";
+//Файлы результатов, которые удаляются из папки вывода перед новым расчетом
+pub const OUTPUT_FILES: &[&str] = &[
+    "rvout.dat",
+    "grsize.dat",
+    "events.dat",
+    "tensors.dat",
+    "volume.dat",
+    "recryst.dat",
+    "subgrains.dat",
+    "orient.dat",
+    "pole_fig100.dat",
+    "pole_fig110.dat",
+    "pole_fig111.dat",
+    "manifest.json",
+];
+//Файлы временных рядов и номер столбца со временем: при продолжении расчета с контрольной
+//точки из них удаляются строки, записанные после сохранения контрольной точки (заголовки "#" сохраняются)
+pub const TIME_SERIES_FILES: &[(&str, usize)] =
+    &[("rvout.dat", 2), ("grsize.dat", 2), ("events.dat", 0), ("tensors.dat", 0), ("volume.dat", 0), ("recryst.dat", 0), ("subgrains.dat", 0)];
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+pub const MANIFEST_FILE: &str = "manifest.json";
+//Папка снимков состояния зерен (файлы grains_<шаг>.csv и grains_<шаг>.npy)
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+//Радиус, которым отмечаются исчезнувшие субзерна (ставшие зародышами или поглощенные при укрупнении)
+pub const SUBGRAIN_REMOVED_R: f64 = 1.0e-17;
 
 //математические константы
 pub const SQRT3: f64 = 1.7320508075688772;
-pub const SQRT2: f64 = 1.4142135623730952;
+pub const SQRT2: f64 = std::f64::consts::SQRT_2;
 
 //коэффициенты перевода
 pub const DECA:f64 = 1.0e1;
@@ -0,0 +1,233 @@
+//C интерфейс материальной точки для встраивания модели в внешние МКЭ программы (C/Fortran).
+//Все тензоры передаются построчно (row-major), векторы Фойгта в порядке 11, 22, 33, 12, 13, 23.
+//Функции возвращают MMUVP_OK при успехе или отрицательный код ошибки.
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::{c_char, c_double, c_int, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use nalgebra::Matrix3;
+
+use crate::{
+    material_point::MaterialPoint,
+    mmuvp::params::systems::from_json_str,
+};
+
+pub const MMUVP_OK: c_int = 0;
+pub const MMUVP_ERROR_NULL_POINTER: c_int = -1;
+pub const MMUVP_ERROR_INVALID_INPUT: c_int = -2;
+pub const MMUVP_ERROR_PANIC: c_int = -3;
+
+//Непрозрачный указатель на состояние материальной точки
+pub struct MmuvpState {
+    material_point: MaterialPoint,
+}
+
+const VOIGT_INDEX: [(usize, usize); 6] = [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)];
+
+fn read_matrix(values: &[c_double]) -> Matrix3<f64> {
+    Matrix3::from_row_slice(values)
+}
+
+fn write_voigt(tensor: &Matrix3<f64>, out: &mut [c_double]) {
+    for (index, &(i, j)) in VOIGT_INDEX.iter().enumerate() {
+        out[index] = tensor[(i, j)];
+    }
+}
+
+unsafe fn read_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    CStr::from_ptr(value).to_str().ok()
+}
+
+//Создает материальную точку по строке JSON с параметрами модели (содержимое param.json).
+//Возвращает NULL при ошибке разбора параметров.
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_create(params_json: *const c_char) -> *mut MmuvpState {
+    let Some(json) = read_str(params_json) else {
+        return ptr::null_mut();
+    };
+    let result = catch_unwind(|| {
+        let params = from_json_str(json).ok()?;
+        Some(MaterialPoint::new(params))
+    });
+    match result {
+        Ok(Some(material_point)) => Box::into_raw(Box::new(MmuvpState { material_point })),
+        _ => ptr::null_mut(),
+    }
+}
+
+//Шаг по заданному градиенту скорости grad_v[9]; напряжения Коши записываются в stress[6]
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_step_grad_v(
+    state: *mut MmuvpState,
+    grad_v: *const c_double,
+    dt: c_double,
+    stress: *mut c_double,
+) -> c_int {
+    if state.is_null() || grad_v.is_null() || stress.is_null() {
+        return MMUVP_ERROR_NULL_POINTER;
+    }
+    if !(dt > 0.0 && dt.is_finite()) {
+        return MMUVP_ERROR_INVALID_INPUT;
+    }
+    let state = &mut *state;
+    let grad_v = read_matrix(slice::from_raw_parts(grad_v, 9));
+    let stress = slice::from_raw_parts_mut(stress, 6);
+    match catch_unwind(AssertUnwindSafe(|| state.material_point.step(grad_v, dt))) {
+        Ok(sigma) => {
+            write_voigt(&sigma, stress);
+            MMUVP_OK
+        }
+        Err(_) => MMUVP_ERROR_PANIC,
+    }
+}
+
+//Шаг в стиле UMAT: приращение деформации dstran[6] (сдвиги инженерные) за время dt.
+//Напряжения записываются в stress[6], касательный модуль dσ/dΔε в ddsdde[36] построчно.
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_step(
+    state: *mut MmuvpState,
+    dstran: *const c_double,
+    dt: c_double,
+    stress: *mut c_double,
+    ddsdde: *mut c_double,
+) -> c_int {
+    if state.is_null() || dstran.is_null() || stress.is_null() || ddsdde.is_null() {
+        return MMUVP_ERROR_NULL_POINTER;
+    }
+    if !(dt > 0.0 && dt.is_finite()) {
+        return MMUVP_ERROR_INVALID_INPUT;
+    }
+    let state = &mut *state;
+    let dstran = slice::from_raw_parts(dstran, 6);
+    let stress = slice::from_raw_parts_mut(stress, 6);
+    let ddsdde = slice::from_raw_parts_mut(ddsdde, 36);
+
+    let mut grad_v = Matrix3::zeros();
+    for (index, &(i, j)) in VOIGT_INDEX.iter().enumerate() {
+        if i == j {
+            grad_v[(i, j)] = dstran[index] / dt;
+        } else {
+            grad_v[(i, j)] = dstran[index] / dt / 2.0;
+            grad_v[(j, i)] = dstran[index] / dt / 2.0;
+        }
+    }
+
+    match catch_unwind(AssertUnwindSafe(|| state.material_point.step_with_tangent(grad_v, dt))) {
+        Ok((sigma, tangent)) => {
+            write_voigt(&sigma, stress);
+            for row in 0..6 {
+                for column in 0..6 {
+                    ddsdde[row * 6 + column] = tangent[(row, column)];
+                }
+            }
+            MMUVP_OK
+        }
+        Err(_) => MMUVP_ERROR_PANIC,
+    }
+}
+
+//Текущие напряжения Коши stress[6]
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_get_stress(state: *const MmuvpState, stress: *mut c_double) -> c_int {
+    if state.is_null() || stress.is_null() {
+        return MMUVP_ERROR_NULL_POINTER;
+    }
+    let state = &*state;
+    match catch_unwind(AssertUnwindSafe(|| state.material_point.get_stress())) {
+        Ok(sigma) => {
+            write_voigt(&sigma, slice::from_raw_parts_mut(stress, 6));
+            MMUVP_OK
+        }
+        Err(_) => MMUVP_ERROR_PANIC,
+    }
+}
+
+//Количество зерен в агрегате (включая зародившиеся при рекристаллизации) записывается в count
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_grain_count(state: *const MmuvpState, count: *mut i32) -> c_int {
+    if state.is_null() || count.is_null() {
+        return MMUVP_ERROR_NULL_POINTER;
+    }
+    let state = &*state;
+    match catch_unwind(AssertUnwindSafe(|| state.material_point.get_simulation().grain_count())) {
+        Ok(grains) => {
+            *count = grains as i32;
+            MMUVP_OK
+        }
+        Err(_) => MMUVP_ERROR_PANIC,
+    }
+}
+
+//Сериализует состояние в строку JSON. Строку необходимо освободить через mmuvp_string_free.
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_serialize(state: *const MmuvpState) -> *mut c_char {
+    if state.is_null() {
+        return ptr::null_mut();
+    }
+    let state = &*state;
+    match catch_unwind(AssertUnwindSafe(|| state.material_point.save_state())) {
+        Ok(json) => CString::new(json).map_or(ptr::null_mut(), CString::into_raw),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+//Восстанавливает состояние из строки, полученной mmuvp_serialize. Возвращает NULL при ошибке.
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_deserialize(data: *const c_char) -> *mut MmuvpState {
+    let Some(json) = read_str(data) else {
+        return ptr::null_mut();
+    };
+    match catch_unwind(|| MaterialPoint::load_state(json)) {
+        Ok(Ok(material_point)) => Box::into_raw(Box::new(MmuvpState { material_point })),
+        _ => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_string_free(data: *mut c_char) {
+    if !data.is_null() {
+        drop(CString::from_raw(data));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn mmuvp_free(state: *mut MmuvpState) {
+    if !state.is_null() {
+        drop(Box::from_raw(state));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmuvp::params::systems::SmallParams;
+
+    #[test]
+    fn rejects_null_pointers_and_invalid_time_step() {
+        unsafe {
+            let mut count = 0;
+            assert_eq!(mmuvp_grain_count(ptr::null(), &mut count), MMUVP_ERROR_NULL_POINTER);
+
+            let params = CString::new(serde_json::to_string(&SmallParams::new(2, 10).seed(7).build()).unwrap()).unwrap();
+            let state = mmuvp_create(params.as_ptr());
+            assert!(!state.is_null());
+            assert_eq!(mmuvp_grain_count(state, ptr::null_mut()), MMUVP_ERROR_NULL_POINTER);
+            assert_eq!(mmuvp_grain_count(state, &mut count), MMUVP_OK);
+            assert_eq!(count, 2);
+            let grad_v = [0.0; 9];
+            let mut stress = [0.0; 6];
+            for dt in [0.0, -1.0e-3, f64::NAN, f64::INFINITY] {
+                let code = mmuvp_step_grad_v(state, grad_v.as_ptr(), dt, stress.as_mut_ptr());
+                assert_eq!(code, MMUVP_ERROR_INVALID_INPUT);
+            }
+            mmuvp_free(state);
+        }
+    }
+}
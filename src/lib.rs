@@ -0,0 +1,14 @@
+//Библиотека модели: все модули, компоненты и системы доступны внешним программам.
+//Компоненты создаются через new(), как и в исполняемом файле, поэтому Default не реализуется.
+#![allow(clippy::new_without_default, clippy::len_without_is_empty)]
+pub mod mmuvp;
+pub mod consts;
+pub mod base_fn;
+pub mod simulation;
+pub mod material_point;
+pub mod ffi;
+pub mod sweep;
+pub mod snapshot;
+pub mod manifest;
+#[cfg(feature = "python")]
+pub mod python;
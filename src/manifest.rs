@@ -0,0 +1,197 @@
+//Манифест расчета manifest.json: итоговые параметры, версия программы, seed, время начала и
+//окончания, время работы подсистем, число зерен и список файлов результатов.
+//Записывается в папку вывода по окончании каждого расчета (в том числе расчетов исследования).
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    consts::{CHECKPOINT_FILE, MANIFEST_FILE, OUTPUT_FILES, SNAPSHOT_DIR},
+    mmuvp::{
+        params::components::{OutputParams, Params},
+        recrystallization::components::JmakFit,
+    },
+    simulation::Simulation,
+};
+
+//Суммарное время работы подсистем расчета, с
+//Сохраняется в контрольной точке и продолжает накапливаться после перезапуска
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SubsystemTimings {
+    seconds: BTreeMap<String, f64>,
+}
+
+impl SubsystemTimings {
+    pub fn new() -> Self {
+        SubsystemTimings { seconds: BTreeMap::new() }
+    }
+    pub fn add(&mut self, name: &str, duration: Duration) {
+        *self.seconds.entry(name.to_string()).or_insert(0.0) += duration.as_secs_f64();
+    }
+    //Добавляет время с момента timer и перезапускает timer для следующей подсистемы
+    pub fn lap(&mut self, name: &str, timer: &mut Instant) {
+        let now = Instant::now();
+        self.add(name, now - *timer);
+        *timer = now;
+    }
+    pub fn get_values(&self) -> &BTreeMap<String, f64> {
+        &self.seconds
+    }
+}
+
+//Сведения о расчете в целом: время и шаг начала, время работы до последней контрольной точки
+//и шаг последнего перезапуска. Сохраняются в контрольной точке.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunHistory {
+    pub start_time: Option<SystemTime>,
+    pub start_step: i64,
+    pub wall_time: f64,
+    pub restarted_from_step: Option<i64>,
+    #[serde(skip, default = "Instant::now")]
+    timer: Instant,
+}
+
+impl Default for RunHistory {
+    fn default() -> Self {
+        RunHistory { start_time: None, start_step: 0, wall_time: 0.0, restarted_from_step: None, timer: Instant::now() }
+    }
+}
+
+impl RunHistory {
+    //Начало участка расчета: первый запуск или продолжение с контрольной точки
+    pub fn begin(&mut self, step: i64) {
+        if self.start_time.is_none() {
+            self.start_time = Some(SystemTime::now());
+            self.start_step = step;
+        } else {
+            self.restarted_from_step = Some(step);
+        }
+        self.timer = Instant::now();
+    }
+    //Добавляет время работы с предыдущего вызова
+    pub fn lap(&mut self) {
+        let now = Instant::now();
+        self.wall_time += (now - self.timer).as_secs_f64();
+        self.timer = now;
+    }
+}
+
+#[derive(Serialize)]
+pub struct GrainCounts {
+    pub initial: usize,
+    pub r#final: usize,
+    pub recrystallized: usize,
+}
+
+#[derive(Serialize)]
+pub struct Manifest {
+    pub crate_version: String,
+    pub git_hash: String,
+    pub seed: Option<u64>,
+    pub start_time: String,
+    pub end_time: String,
+    pub start_step: i64,
+    //Шаг последнего продолжения с контрольной точки (null, если расчет не прерывался)
+    pub restarted_from_step: Option<i64>,
+    pub end_step: i64,
+    pub wall_time: f64,
+    pub subsystem_time: BTreeMap<String, f64>,
+    pub grains: GrainCounts,
+    pub initial_volume: f64,
+    pub max_volume_error: f64,
+    //Аппроксимация кинетики рекристаллизации уравнением JMAK (null, если недостаточно точек)
+    pub jmak: Option<JmakFit>,
+    pub output_files: Vec<String>,
+    pub config: Params,
+}
+
+impl Manifest {
+    //Время и шаг начала, время работы подсистем относятся ко всему расчету, включая участки
+    //до перезапусков; history.lap() должен быть вызван перед созданием манифеста
+    pub fn new(simulation: &Simulation, jmak: Option<JmakFit>) -> Self {
+        let end_time = SystemTime::now();
+        let history = &simulation.run_history;
+        Manifest {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: env!("MMUVP_GIT_HASH").to_string(),
+            seed: simulation.params.seed,
+            start_time: format_utc(history.start_time.unwrap_or(end_time)),
+            end_time: format_utc(end_time),
+            start_step: history.start_step,
+            restarted_from_step: history.restarted_from_step,
+            end_step: simulation.step,
+            wall_time: history.wall_time,
+            subsystem_time: simulation.timings.get_values().clone(),
+            grains: GrainCounts {
+                initial: simulation.params.polycrystal.grain_num,
+                r#final: simulation.grain_count(),
+                recrystallized: simulation.status_map.values().filter(|status| status.get_value()).count(),
+            },
+            initial_volume: simulation.initial_volume,
+            max_volume_error: simulation.max_volume_error,
+            jmak,
+            output_files: list_output_files(&simulation.output_path, &simulation.params.output),
+            config: simulation.params.clone(),
+        }
+    }
+
+    pub fn write(&self, output_path: &Path) -> io::Result<()> {
+        let json_string = serde_json::to_string_pretty(self)?;
+        fs::write(output_path.join(MANIFEST_FILE), json_string)
+    }
+}
+
+//Файлы результатов, которые есть в папке вывода (пути относительно папки вывода)
+pub fn list_output_files(output_path: &Path, output: &OutputParams) -> Vec<String> {
+    let mut names: Vec<String> = OUTPUT_FILES
+        .iter()
+        .chain([CHECKPOINT_FILE].iter())
+        .filter(|name| **name != MANIFEST_FILE)
+        .map(|name| name.to_string())
+        .collect();
+    for step in output.snapshot_steps.iter() {
+        for extension in ["csv", "npy"] {
+            names.push(format!("{}/grains_{:06}.{}", SNAPSHOT_DIR, step, extension));
+        }
+    }
+    for step in output.grain_size_steps.iter() {
+        names.push(format!("{}/grain_size_{:06}.dat", SNAPSHOT_DIR, step));
+    }
+    names.retain(|name| output_path.join(name).is_file());
+    names
+}
+
+//Время в формате ISO 8601 (UTC), например 2024-05-01T12:00:00Z
+pub fn format_utc(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let (days, rest) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    //Перевод числа дней от 1970-01-01 в дату григорианского календаря
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
@@ -0,0 +1,196 @@
+#![allow(dead_code)]
+
+use nalgebra::{Matrix3, Matrix6};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mmuvp::{
+        elasticity::systems::calc_mean_sigma,
+        params::components::Params,
+    },
+    simulation::{Simulation, SimulationBuilder},
+};
+
+//Порядок компонент Фойгта совпадает с get_vector(): 11, 22, 33, 12, 13, 23
+const VOIGT_INDEX: [(usize, usize); 6] = [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)];
+//Приращение деформации для численного дифференцирования касательного модуля
+const PERTURBATION: f64 = 1.0e-6;
+
+//Материальная точка: один поликристаллический агрегат со всеми зернами и их компонентами.
+//Используется как определяющее соотношение (аналог UMAT): на вход градиент скорости и шаг по времени,
+//на выходе тензор напряжений Коши (МПа) и касательный модуль dσ/dΔε (МПа).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MaterialPoint {
+    simulation: Simulation,
+    sigma: Matrix3<f64>,
+}
+
+impl MaterialPoint {
+    pub fn new(params: Params) -> Self {
+        //Внутри конечно-элементного расчета сообщения о ходе расчета не выводятся
+        let simulation = SimulationBuilder::new(params).quiet(true).build();
+        MaterialPoint {
+            simulation,
+            sigma: Matrix3::zeros(),
+        }
+    }
+
+    //Шаг с заданным макроскопическим градиентом скорости
+    pub fn step(&mut self, grad_v: Matrix3<f64>, dt: f64) -> Matrix3<f64> {
+        self.simulation.advance(grad_v, dt);
+        self.sigma = calc_mean_sigma(&self.simulation.sigma_map, &self.simulation.rotation_map, &self.simulation.gr_size_map);
+        self.sigma
+    }
+
+    //Шаг с заданными градиентами деформации в начале и в конце шага.
+    //Градиент скорости вычисляется по средней конфигурации: L = ΔF·F_mid^-1/dt.
+    //При недопустимом шаге по времени или вырожденном F_mid состояние не меняется
+    pub fn step_deformation_gradient(
+        &mut self,
+        f_old: Matrix3<f64>,
+        f_new: Matrix3<f64>,
+        dt: f64,
+    ) -> Result<Matrix3<f64>, String> {
+        if !(dt > 0.0 && dt.is_finite()) {
+            return Err(format!("шаг по времени должен быть > 0, получено {}", dt));
+        }
+        let f_mid = (f_old + f_new) / 2.0;
+        let f_mid_inv = f_mid
+            .try_inverse()
+            .ok_or_else(|| "вырожденный градиент деформации".to_string())?;
+        let grad_v = (f_new - f_old) * f_mid_inv / dt;
+        if grad_v.iter().any(|value| !value.is_finite()) {
+            return Err("градиент деформации содержит нечисловые значения".to_string());
+        }
+        Ok(self.step(grad_v, dt))
+    }
+
+    //Шаг с вычислением касательного модуля возмущением приращения деформации.
+    //Для каждой компоненты Фойгта шаг повторяется на копии состояния, сдвиговые компоненты
+    //возмущаются на инженерную деформацию (по половине в ij и ji).
+    pub fn step_with_tangent(&mut self, grad_v: Matrix3<f64>, dt: f64) -> (Matrix3<f64>, Matrix6<f64>) {
+        let mut tangent = Matrix6::zeros();
+        for (column, &(i, j)) in VOIGT_INDEX.iter().enumerate() {
+            let mut perturbation = Matrix3::zeros();
+            if i == j {
+                perturbation[(i, j)] = PERTURBATION / dt;
+            } else {
+                perturbation[(i, j)] = PERTURBATION / dt / 2.0;
+                perturbation[(j, i)] = PERTURBATION / dt / 2.0;
+            }
+            let mut perturbed = self.clone();
+            let sigma_perturbed = perturbed.step(grad_v + perturbation, dt);
+            for (row, &(k, l)) in VOIGT_INDEX.iter().enumerate() {
+                tangent[(row, column)] = sigma_perturbed[(k, l)];
+            }
+        }
+
+        let sigma = self.step(grad_v, dt);
+        for (row, &(k, l)) in VOIGT_INDEX.iter().enumerate() {
+            for column in 0..6 {
+                tangent[(row, column)] = (tangent[(row, column)] - sigma[(k, l)]) / PERTURBATION;
+            }
+        }
+        (sigma, tangent)
+    }
+
+    pub fn get_stress(&self) -> Matrix3<f64> {
+        self.sigma
+    }
+
+    pub fn get_simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    //Сериализация полного внутреннего состояния (все зерна, параметры, номер шага) в JSON
+    pub fn save_state(&self) -> String {
+        serde_json::to_string(self).expect("Ошибка сериализации состояния материальной точки")
+    }
+
+    pub fn load_state(state: &str) -> Result<Self, serde_json::Error> {
+        let mut point: MaterialPoint = serde_json::from_str(state)?;
+        point.simulation.quiet = true;
+        Ok(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmuvp::{params::systems::SmallParams, standart_deformation::uniaxial_tension};
+
+    fn small_params() -> Params {
+        SmallParams::new(4, 20).seed(7).build()
+    }
+
+    #[test]
+    fn elastic_tangent_matches_elasticity_matrix() {
+        //Компоненты Фойгта скоростей деформации - тензорные, поэтому кристалл изотропен при c44 = c11 - c12,
+        //и касательный модуль поликристалла не зависит от ориентаций зерен
+        let mut params = small_params();
+        params.elasticity.c44 = params.elasticity.c11 - params.elasticity.c12;
+        let (c11, c12, c44, koef) = (params.elasticity.c11, params.elasticity.c12, params.elasticity.c44, params.elasticity.koef);
+        let mut point = MaterialPoint::new(params);
+        let (sigma, tangent) = point.step_with_tangent(Matrix3::zeros(), 1.0e-3);
+        assert_eq!(sigma, Matrix3::zeros());
+
+        //Касательный модуль по инженерным сдвигам: на диагонали сдвиговой части c44/2
+        let mut expected = Matrix6::zeros();
+        for i in 0..3 {
+            for j in 0..3 {
+                expected[(i, j)] = if i == j { c11 } else { c12 };
+            }
+            expected[(i + 3, i + 3)] = c44 / 2.0;
+        }
+        expected *= koef;
+        for row in 0..6 {
+            for column in 0..6 {
+                let error = (tangent[(row, column)] - expected[(row, column)]).abs();
+                assert!(error < 1.0e-6 * c11, "tangent[{}, {}] = {}, expected {}", row, column, tangent[(row, column)], expected[(row, column)]);
+            }
+        }
+    }
+
+    //Порядок зерен в JSON зависит от HashMap, поэтому состояния сравниваются как значения JSON
+    //Время работы подсистем зависит от машины и в сравнении состояний не участвует
+    fn state_value(point: &MaterialPoint) -> serde_json::Value {
+        let mut value: serde_json::Value = serde_json::from_str(&point.save_state()).unwrap();
+        value["simulation"].as_object_mut().unwrap().remove("timings");
+        value
+    }
+
+    #[test]
+    fn saved_state_continues_identically() {
+        let grad_v = uniaxial_tension(1.0e-2);
+        let mut point = MaterialPoint::new(small_params());
+        for _ in 0..500 {
+            point.step(grad_v, 1.0e-2);
+        }
+        let mut restored = MaterialPoint::load_state(&point.save_state()).unwrap();
+        assert_eq!(state_value(&restored), state_value(&point));
+
+        let sigma = point.step(grad_v, 1.0e-2);
+        let sigma_restored = restored.step(grad_v, 1.0e-2);
+        assert_eq!(sigma, sigma_restored);
+        assert_eq!(state_value(&restored), state_value(&point));
+    }
+
+    #[test]
+    fn invalid_deformation_gradient_is_rejected() {
+        let mut point = MaterialPoint::new(small_params());
+        let state = state_value(&point);
+        let identity = Matrix3::identity();
+        //F_mid = (F_old + F_new)/2 с нулевой третьей строкой
+        let singular = Matrix3::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
+        assert!(point.step_deformation_gradient(singular, singular, 1.0e-3).is_err());
+        for dt in [0.0, -1.0e-3, f64::NAN, f64::INFINITY] {
+            assert!(point.step_deformation_gradient(identity, identity, dt).is_err());
+        }
+        assert_eq!(state_value(&point), state);
+
+        let mut stretched = identity;
+        stretched[(0, 0)] = 1.0 + 1.0e-5;
+        let sigma = point.step_deformation_gradient(identity, stretched, 1.0e-3).unwrap();
+        assert!(sigma[(0, 0)] > 0.0);
+    }
+}
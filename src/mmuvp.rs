@@ -4,4 +4,7 @@ pub mod rotation;
 pub mod elasticity;
 pub mod slide_system;
 pub mod standart_deformation;
-pub mod recrystallization;
\ No newline at end of file
+pub mod recrystallization;
+pub mod recovery;
+pub mod events;
+pub mod random;
\ No newline at end of file
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
+use serde::{Deserialize, Serialize};
 
 use nalgebra::{Matrix3, Vector6, Matrix6};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GradVComponent {
     tensor: Matrix3<f64>,
 }
@@ -22,6 +24,7 @@ impl GradVComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DComponent {
     tensor: Matrix3<f64>,
     vector: Vector6<f64>,
@@ -61,6 +64,7 @@ impl DComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WComponent {
     tensor: Matrix3<f64>,
 }
@@ -82,6 +86,7 @@ impl WComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TrajectoryDeformationComponent {
     time: f64,
     grad_v: Matrix3<f64>,
@@ -104,6 +109,7 @@ impl TrajectoryDeformationComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EpsComponent {
     tensor: Matrix3<f64>,
     vector: Vector6<f64>,
@@ -143,6 +149,7 @@ impl EpsComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SigmaComponent {
     tensor: Matrix3<f64>,
     vector: Vector6<f64>,
@@ -182,6 +189,7 @@ impl SigmaComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SigmaRateComponent {
     tensor: Matrix3<f64>,
     vector: Vector6<f64>,
@@ -221,6 +229,7 @@ impl SigmaRateComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ElasticityTensorComponent {
     value: Matrix6<f64>,
 }
@@ -5,15 +5,16 @@ use std::{
     collections::HashMap, 
     fs::{File, OpenOptions}, 
     io::{self, BufRead, BufWriter, Write}, 
-    path::PathBuf};
+    path::Path};
 
 use nalgebra::{Matrix3, Matrix6, Vector6};
 
 use crate::{mmuvp::{
-    entity::CrystalEntity, 
+    entity::{sorted_entities, CrystalEntity}, 
     rotation::components::*, 
-    slide_system::components::*
-}, consts::{FILE_INPUT_PATH, FILE_OUTPUT_PATH, MEGA}, GrainSizeComponent};
+    slide_system::components::*,
+    recrystallization::components::GrainSizeComponent
+}};
 
 use super::components::*;
 
@@ -38,9 +39,10 @@ pub fn initialize_grad_v(
 
 pub fn read_grad_v_from_file_with_6_comp(
     trajectory_deformation: &mut Vec<TrajectoryDeformationComponent>,
+    input_path: &Path,
 ) 
 {
-    let file = File::open(PathBuf::from(FILE_INPUT_PATH).join("grad_v.input")).expect("Не удалось открыть файл grad_v.input");
+    let file = File::open(input_path.join("grad_v.input")).expect("Не удалось открыть файл grad_v.input");
     let reader = io::BufReader::new(file);
 
     //let mut tensor_data_list = Vec::new();
@@ -122,21 +124,39 @@ pub fn calc_eps(
     })
 }
 
+//Осреднение по объему: вклад зерна пропорционален кубу его радиуса
 pub fn calc_mean_eps(
     eps_map: &HashMap<CrystalEntity, EpsComponent>,
     rotation_map: &HashMap<CrystalEntity, RotationComponent>,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+) -> Matrix3<f64> {
+    calc_volume_mean(eps_map, rotation_map, gr_size_map, |e_component| e_component.get_tensor())
+}
+
+//Осредненный по объему поликристалла тензор в лабораторной системе координат
+fn calc_volume_mean<T>(
+    map: &HashMap<CrystalEntity, T>,
+    rotation_map: &HashMap<CrystalEntity, RotationComponent>,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    get_tensor: impl Fn(&T) -> Matrix3<f64>,
 ) -> Matrix3<f64> {
+    let mut summ = 0.0;
     let mut mean_matrix = Matrix3::zeros();
-    for (entity, e_component) in eps_map.iter() {
-        if let Some(orient_component) = rotation_map.get(entity) {
-            mean_matrix += orient_component.get_tensor()
-                * e_component.get_tensor()
-                * orient_component.get_tensor().transpose();
-        } else {
-            panic!("Ошибка поиска тензора ориентации");
-        }
+    for entity in sorted_entities(map) {
+        let orient_component = rotation_map.get(entity).expect("Ошибка поиска тензора ориентации");
+        let gr_size_component = gr_size_map.get(entity).expect("Ошибка поиска компонента gr_size");
+        let weight = gr_size_component.get_value().powi(3);
+        summ += weight;
+        mean_matrix += orient_component.get_tensor()
+            * get_tensor(&map[entity])
+            * orient_component.get_tensor().transpose()
+            * weight;
+    }
+    if summ > 0.0 {
+        mean_matrix / summ
+    } else {
+        mean_matrix
     }
-    (1.0 / eps_map.len() as f64) * mean_matrix
 }
 
 pub fn calc_intensity_eps(din_component: &EpsComponent) -> f64 {
@@ -165,18 +185,9 @@ pub fn calc_sigma(
 pub fn calc_mean_sigma(
     sigma_map: &HashMap<CrystalEntity, SigmaComponent>,
     rotation_map: &HashMap<CrystalEntity, RotationComponent>,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
 ) -> Matrix3<f64> {
-    let mut mean_matrix = Matrix3::zeros();
-    for (entity, sigma_component) in sigma_map.iter() {
-        if let Some(orient_component) = rotation_map.get(entity) {
-            mean_matrix += orient_component.get_tensor()
-                * sigma_component.get_tensor()
-                * orient_component.get_tensor().transpose();
-        } else {
-            panic!("Ошибка поиска тензора ориентации");
-        }
-    }
-    (1.0 / sigma_map.len() as f64) * mean_matrix
+    calc_volume_mean(sigma_map, rotation_map, gr_size_map, |sigma_component| sigma_component.get_tensor())
 }
 
 pub fn calc_mean_sigma_with_weidth(
@@ -186,7 +197,8 @@ pub fn calc_mean_sigma_with_weidth(
 ) -> Matrix3<f64> {
     let mut summ=0.0;
     let mut mean_matrix = Matrix3::zeros();
-    for (entity, sigma_component) in sigma_map.iter() {
+    for entity in sorted_entities(sigma_map) {
+        let sigma_component = &sigma_map[entity];
         if let Some(orient_component) = rotation_map.get(entity) {
             if let Some(gr_size_component) = gr_size_map.get(entity){
                 let sigma_tensor = sigma_component.get_tensor()*gr_size_component.get_value();
@@ -210,22 +222,21 @@ pub fn calc_intensity_s(sigma_component: &SigmaComponent) -> f64 {
 pub fn write_intensity_to_file(
     polycrystal_eps: &EpsComponent,
     polycrystal_sigma: &SigmaComponent,
-    step: i64,
-    dt: f64,
+    time: f64,
+    output_path: &Path,
 ) {
     let file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(PathBuf::from(FILE_OUTPUT_PATH).join("rvout.dat"))
+        .open(output_path.join("rvout.dat"))
         .expect("Ошибка открытия файла rvout.dat");
-    //let file = File::create(FILE_OUTPUT_PATH.to_string() + "din.dat")?;
     let mut buf_writer = BufWriter::with_capacity(4 * (10 + 1 + 10 + 1) * 3, file);
 
     write!(buf_writer, "{:.4e}\t", calc_intensity_eps(polycrystal_eps))
         .expect("Ошибка записи интенсивности деформации в rvout.dat");
     write!(buf_writer, "{:.4e}\t", calc_intensity_s(polycrystal_sigma))
         .expect("Ошибка записи интенсивности напряжения в rvout.dat");
-    write!(buf_writer, "{}\t", dt * step as f64).expect("Ошибка записи времени в rvout.dat");
+    write!(buf_writer, "{}\t", time).expect("Ошибка записи времени в rvout.dat");
 
     writeln!(buf_writer).expect("Ошибка записи разделителя в rvout.dat");
 
@@ -235,6 +246,108 @@ pub fn write_intensity_to_file(
         .expect("Ошибка завершения записи в rvout.dat");
 }
 
+//Осредненный по объему поликристалла тензор скорости деформации (полной, упругой или неупругой)
+//в лабораторной системе координат
+pub fn calc_mean_d(
+    d_map: &HashMap<CrystalEntity, DComponent>,
+    rotation_map: &HashMap<CrystalEntity, RotationComponent>,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+) -> Matrix3<f64> {
+    calc_volume_mean(d_map, rotation_map, gr_size_map, |d_component| d_component.get_tensor())
+}
+
+//Давление p = -tr(sigma)/3
+pub fn calc_pressure(sigma: &Matrix3<f64>) -> f64 {
+    -sigma.trace() / 3.0
+}
+
+//Эквивалентное напряжение по Мизесу sqrt(3/2 s:s), s - девиатор напряжений
+pub fn calc_von_mises(sigma: &Matrix3<f64>) -> f64 {
+    let s = sigma - Matrix3::identity() * (sigma.trace() / 3.0);
+    (s.dot(&s) * 3.0 / 2.0).sqrt()
+}
+
+//Коэффициент трехосности: отношение среднего напряжения к эквивалентному
+pub fn calc_triaxiality(sigma: &Matrix3<f64>) -> f64 {
+    let sigma_eq = calc_von_mises(sigma);
+    if sigma_eq == 0.0 {
+        return f64::NAN;
+    }
+    sigma.trace() / 3.0 / sigma_eq
+}
+
+//Угол Лоде (градусы, от 0 при одноосном растяжении до 60 при одноосном сжатии):
+//cos(3 theta) = 27/2 det(s) / sigma_eq^3
+pub fn calc_lode_angle(sigma: &Matrix3<f64>) -> f64 {
+    let sigma_eq = calc_von_mises(sigma);
+    if sigma_eq == 0.0 {
+        return f64::NAN;
+    }
+    let s = sigma - Matrix3::identity() * (sigma.trace() / 3.0);
+    let cos_3theta = (13.5 * s.determinant() / sigma_eq.powi(3)).clamp(-1.0, 1.0);
+    cos_3theta.acos().to_degrees() / 3.0
+}
+
+//Коэффициент Лэнкфорда при растяжении вдоль оси 1: отношение неупругих скоростей деформации
+//по ширине (ось 2) и толщине (ось 3)
+pub fn calc_lankford(din: &Matrix3<f64>) -> f64 {
+    if din[(2, 2)] == 0.0 {
+        return f64::NAN;
+    }
+    din[(1, 1)] / din[(2, 2)]
+}
+
+//Полные осредненные тензоры и инварианты в tensors.dat. Компоненты тензоров в порядке 11, 22, 33, 12, 13, 23;
+//напряжения в МПа, скорости деформации в 1/с, угол Лоде в градусах
+pub fn write_tensors_to_file(
+    polycrystal_sigma: &Matrix3<f64>,
+    polycrystal_eps: &Matrix3<f64>,
+    polycrystal_din: &Matrix3<f64>,
+    polycrystal_de: &Matrix3<f64>,
+    time: f64,
+    output_path: &Path,
+) {
+    let path = output_path.join("tensors.dat");
+    let new_file = path.metadata().map_or(true, |metadata| metadata.len() == 0);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .expect("Ошибка открытия файла tensors.dat");
+    let mut buf_writer = BufWriter::new(file);
+
+    let voigt = [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)];
+    if new_file {
+        let mut header = vec!["time".to_string()];
+        for name in ["sigma", "eps", "din", "de"] {
+            header.extend(voigt.iter().map(|(i, j)| format!("{}_{}{}", name, i + 1, j + 1)));
+        }
+        header.extend(["pressure", "lode_angle", "triaxiality", "lankford_r"].map(String::from));
+        writeln!(buf_writer, "# {}", header.join("\t")).expect("Ошибка записи заголовка в tensors.dat");
+    }
+
+    write!(buf_writer, "{}", time).expect("Ошибка записи времени в tensors.dat");
+    for tensor in [polycrystal_sigma, polycrystal_eps, polycrystal_din, polycrystal_de] {
+        for (i, j) in voigt.iter() {
+            write!(buf_writer, "\t{:.6e}", tensor[(*i, *j)]).expect("Ошибка записи тензора в tensors.dat");
+        }
+    }
+    write!(
+        buf_writer,
+        "\t{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}",
+        calc_pressure(polycrystal_sigma),
+        calc_lode_angle(polycrystal_sigma),
+        calc_triaxiality(polycrystal_sigma),
+        calc_lankford(polycrystal_din)
+    )
+    .expect("Ошибка записи инвариантов в tensors.dat");
+    writeln!(buf_writer).expect("Ошибка записи разделителя в tensors.dat");
+
+    buf_writer
+        .flush()
+        .expect("Ошибка завершения записи в tensors.dat");
+}
+
 pub fn calc_de_elastic_deform(
     de_map: &mut HashMap<CrystalEntity, DComponent>,
     d_map: &HashMap<CrystalEntity, DComponent>,
@@ -270,9 +383,6 @@ pub fn get_elasticity_tensor_fcc(
     c44: f64,
     koef:f64,
 ){
-    let c11 = c11 / MEGA;//MPa
-    let c12 = c12 / MEGA;//MPa
-    let c44 = c44 / MEGA;//MPa
     let value = Matrix6::new(
         c11, c12, c12, 0.0, 0.0, 0.0, c12, c11, c12, 0.0, 0.0, 0.0, c12, c12, c11, 0.0, 0.0,
         0.0, 0.0, 0.0, 0.0, c44, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, c44, 0.0, 0.0, 0.0, 0.0, 0.0,
@@ -288,9 +398,6 @@ pub fn initialize_elasticity_tensor_fcc(
     c44: f64,
     koef:f64,
 ) {
-    let c11 = c11 / MEGA;//MPa
-    let c12 = c12 / MEGA;//MPa
-    let c44 = c44 / MEGA;//MPa
     for c_tensor in elasticity_map.values_mut() {
         let value = Matrix6::new(
             c11, c12, c12, 0.0, 0.0, 0.0, c12, c11, c12, 0.0, 0.0, 0.0, c12, c12, c11, 0.0, 0.0,
@@ -347,4 +454,35 @@ pub fn calc_din(
             panic!("Ошибка поиска компонента gamma_rate");
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn stress_invariants_of_simple_states() {
+        let tension = Matrix3::from_diagonal(&Vector3::new(100.0, 0.0, 0.0));
+        assert!((calc_von_mises(&tension) - 100.0).abs() < 1.0e-12);
+        assert!((calc_triaxiality(&tension) - 1.0 / 3.0).abs() < 1.0e-12);
+        assert!(calc_lode_angle(&tension).abs() < 1.0e-6);
+        //Гидростатическое давление не меняет угол Лоде
+        assert!(calc_lode_angle(&(tension + Matrix3::identity() * 50.0)).abs() < 1.0e-6);
+
+        let compression = -tension;
+        assert!((calc_lode_angle(&compression) - 60.0).abs() < 1.0e-6);
+        assert!((calc_triaxiality(&compression) + 1.0 / 3.0).abs() < 1.0e-12);
+
+        let shear = Matrix3::from_diagonal(&Vector3::new(100.0, -100.0, 0.0));
+        assert!((calc_lode_angle(&shear) - 30.0).abs() < 1.0e-6);
+
+        assert!(calc_lode_angle(&Matrix3::zeros()).is_nan());
+    }
+
+    #[test]
+    fn lankford_of_isotropic_flow_is_one() {
+        let din = Matrix3::from_diagonal(&Vector3::new(1.0, -0.5, -0.5));
+        assert_eq!(calc_lankford(&din), 1.0);
+        assert!(calc_lankford(&Matrix3::zeros()).is_nan());
+    }
+}
@@ -1,5 +1,10 @@
 #![allow(dead_code)]
-#[derive(Eq, Hash, PartialEq, Clone)]
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Eq, Hash, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct CrystalEntity {
     id: u32,
 }
@@ -13,3 +18,11 @@ impl CrystalEntity {
         self.id
     }
 }
+
+//Сущности в порядке номеров. Порядок обхода HashMap меняется от запуска к запуску,
+//поэтому суммы по зернам и порядок событий вычисляются в этом порядке
+pub fn sorted_entities<T>(map: &HashMap<CrystalEntity, T>) -> Vec<&CrystalEntity> {
+    let mut entities: Vec<&CrystalEntity> = map.keys().collect();
+    entities.sort_by_key(|entity| entity.get_id());
+    entities
+}
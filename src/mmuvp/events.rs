@@ -0,0 +1,2 @@
+pub mod components;
+pub mod systems;
\ No newline at end of file
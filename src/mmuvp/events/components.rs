@@ -0,0 +1,68 @@
+#![allow(dead_code)]
+use serde::{Deserialize, Serialize};
+
+use crate::mmuvp::entity::CrystalEntity;
+
+//События жизненного цикла зерен, которые системы публикуют в очередь
+#[derive(Clone, Serialize, Deserialize)]
+pub enum GrainEvent {
+    GrainNucleated {
+        parent: CrystalEntity,
+        radius: f64,
+        time: f64,
+    },
+    GrainConsumed {
+        entity: CrystalEntity,
+        time: f64,
+    },
+    SlipSystemActivated {
+        entity: CrystalEntity,
+        system: usize,
+        time: f64,
+    },
+}
+
+impl GrainEvent {
+    pub fn get_name(&self) -> &str {
+        match self {
+            GrainEvent::GrainNucleated { .. } => "GrainNucleated",
+            GrainEvent::GrainConsumed { .. } => "GrainConsumed",
+            GrainEvent::SlipSystemActivated { .. } => "SlipSystemActivated",
+        }
+    }
+
+    pub fn get_time(&self) -> f64 {
+        match self {
+            GrainEvent::GrainNucleated { time, .. } => *time,
+            GrainEvent::GrainConsumed { time, .. } => *time,
+            GrainEvent::SlipSystemActivated { time, .. } => *time,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EventQueueComponent {
+    events: Vec<GrainEvent>,
+}
+
+impl EventQueueComponent {
+    pub fn new() -> Self {
+        EventQueueComponent { events: Vec::new() }
+    }
+    pub fn push_event(&mut self, event: GrainEvent) {
+        self.events.push(event);
+    }
+    pub fn get_events(&self) -> &[GrainEvent] {
+        &self.events
+    }
+    //Забирает все накопленные события, очередь при этом очищается
+    pub fn drain(&mut self) -> Vec<GrainEvent> {
+        std::mem::take(&mut self.events)
+    }
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
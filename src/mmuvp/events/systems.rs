@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use super::components::*;
+
+//Строка events.dat: время, тип события, сущность, параметр события ("-", если параметра нет)
+pub fn format_event(event: &GrainEvent) -> String {
+    let (entity, value) = match event {
+        GrainEvent::GrainNucleated { parent, radius, .. } => (parent.get_id(), format!("{:.6e}", radius)),
+        GrainEvent::GrainConsumed { entity, .. } => (entity.get_id(), "-".to_string()),
+        GrainEvent::SlipSystemActivated { entity, system, .. } => (entity.get_id(), system.to_string()),
+    };
+    format!("{:.6e}\t{}\t{}\t{}", event.get_time(), event.get_name(), entity, value)
+}
+
+pub fn write_events_to_file(events: &[GrainEvent], output_path: &Path) {
+    if events.is_empty() {
+        return;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(output_path.join("events.dat"))
+        .expect("Ошибка открытия файла events.dat");
+    let mut buf_writer = BufWriter::new(file);
+
+    for event in events {
+        writeln!(buf_writer, "{}", format_event(event)).expect("Ошибка записи события в events.dat");
+    }
+
+    buf_writer
+        .flush()
+        .expect("Ошибка завершения записи в events.dat");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmuvp::entity::CrystalEntity;
+
+    #[test]
+    fn every_event_has_four_columns() {
+        let events = [
+            GrainEvent::GrainNucleated { parent: CrystalEntity::new(3), radius: 2.0e-6, time: 1.5 },
+            GrainEvent::GrainConsumed { entity: CrystalEntity::new(4), time: 2.0 },
+            GrainEvent::SlipSystemActivated { entity: CrystalEntity::new(5), system: 11, time: 0.25 },
+        ];
+        let lines: Vec<String> = events.iter().map(format_event).collect();
+        assert_eq!(lines[0], "1.500000e0\tGrainNucleated\t3\t2.000000e-6");
+        assert_eq!(lines[1], "2.000000e0\tGrainConsumed\t4\t-");
+        assert_eq!(lines[2], "2.500000e-1\tSlipSystemActivated\t5\t11");
+        for line in lines.iter() {
+            assert_eq!(line.split('\t').count(), 4);
+            assert!(!line.ends_with('\t'));
+        }
+    }
+}
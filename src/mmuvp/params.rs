@@ -1,2 +1,3 @@
 pub mod components;
-pub mod systems;
\ No newline at end of file
+pub mod systems;
+pub mod units;
\ No newline at end of file
@@ -1,45 +1,398 @@
 #![allow(dead_code)]
 extern crate serde_json;
-use serde_json::Value;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
+use super::units;
+
+//Параметры интегрирования по времени
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeParams {
+    #[serde(with = "units::time")]
+    pub dt: f64,
+    pub steps_num: i64,
+    pub write_step: i64,
+}
+
+impl Default for TimeParams {
+    fn default() -> Self {
+        TimeParams { dt: 1e-3, steps_num: 18000, write_step: 200 }
+    }
+}
+
+//Нагружение: одноосное растяжение с постоянной скоростью деформации
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadingParams {
+    #[serde(with = "units::rate")]
+    pub strain_rate: f64,
+}
+
+impl Default for LoadingParams {
+    fn default() -> Self {
+        LoadingParams { strain_rate: 1e-2 }
+    }
+}
+
+//Вид стадии процесса: деформирование с постоянной скоростью или выдержка при температуре
+//с нулевым градиентом скорости
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageKind {
+    #[default]
+    Deformation,
+    Anneal,
+}
+
+//Стадия процесса: длительность, шаг по времени и температура.
+//strain_rate задается для деформирования, relax_stress - для выдержки: true - напряжения
+//релаксируют за счет неупругой деформации (до критических напряжений сдвига), false - снимаются
+//в начале выдержки
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StageParams {
+    pub name: String,
+    pub kind: StageKind,
+    #[serde(with = "units::time")]
+    pub duration: f64,
+    #[serde(with = "units::time")]
+    pub dt: f64,
+    #[serde(with = "units::temperature")]
+    pub temp: f64,
+    #[serde(with = "units::rate")]
+    pub strain_rate: f64,
+    pub relax_stress: bool,
+}
+
+impl Default for StageParams {
+    fn default() -> Self {
+        StageParams {
+            name: String::new(),
+            kind: StageKind::Deformation,
+            duration: 18.0,
+            dt: 1e-3,
+            temp: 773.0,
+            strain_rate: 1e-2,
+            relax_stress: true,
+        }
+    }
+}
+
+impl StageParams {
+    pub fn get_steps(&self) -> i64 {
+        (self.duration / self.dt).round() as i64
+    }
+}
+
+//Начальная зеренная структура поликристалла
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolycrystalParams {
+    pub grain_num: usize,
+    #[serde(with = "units::length")]
+    pub gr_size: f64,
+    #[serde(with = "units::length")]
+    pub std_dev: f64,
+}
+
+impl Default for PolycrystalParams {
+    fn default() -> Self {
+        PolycrystalParams { grain_num: 300, gr_size: 400e-6, std_dev: 100e-6 }
+    }
+}
+
+//Упругие константы ГЦК кристалла
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElasticityParams {
+    #[serde(with = "units::stress")]
+    pub c11: f64,
+    #[serde(with = "units::stress")]
+    pub c12: f64,
+    #[serde(with = "units::stress")]
+    pub c44: f64,
+    pub koef: f64,
+}
+
+impl Default for ElasticityParams {
+    fn default() -> Self {
+        ElasticityParams { c11: 106.8e3, c12: 60.4e3, c44: 28.3e3, koef: 0.1375 }
+    }
+}
+
+//Вязкопластический закон скольжения и начальные критические напряжения (с поправкой Холла-Петча)
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlipParams {
+    #[serde(with = "units::rate")]
+    pub gamma_0: f64,
+    pub m: f64,
+    #[serde(with = "units::stress")]
+    pub tau_c: f64,
+    #[serde(with = "units::stress")]
+    pub k_y: f64,
+    #[serde(with = "units::length")]
+    pub b: f64,
+}
+
+impl Default for SlipParams {
+    fn default() -> Self {
+        SlipParams { gamma_0: 1e-2, m: 83.0, tau_c: 68.0, k_y: 3.9e3, b: 2.5e-10 }
+    }
+}
+
+//Закон упрочнения с насыщением
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HardeningParams {
+    #[serde(with = "units::stress")]
+    pub tau_sat: f64,
+    #[serde(with = "units::stress")]
+    pub h0: f64,
+    pub a: f64,
+    pub qlat: f64,
+}
+
+impl Default for HardeningParams {
+    fn default() -> Self {
+        HardeningParams { tau_sat: 280.0, h0: 300.0, a: 1.01, qlat: 1.4 }
+    }
+}
+
+//Запасенная энергия, определяющая движущую силу зарождения и роста зерен:
+//mean - средняя по поликристаллу (одинаковая для всех зерен), grain - собственная энергия каждого зерна
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StoredEnergyMode {
+    #[default]
+    Mean,
+    Grain,
+}
+
+//Ориентация зародышей рекристаллизации: random - случайная, inherit - ориентация исходного зерна,
+//cube - кубическая ориентация {100}<001>, twin - двойник Σ3 исходного зерна (поворот на 60°
+//вокруг одной из осей <111>)
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NucleusOrientationModel {
+    #[default]
+    Random,
+    Inherit,
+    Cube,
+    Twin,
+}
+
+//scatter - стандартное отклонение каждой компоненты вектора поворота от ориентации модели,
+//fraction - доля зародышей с ориентацией по модели, остальные получают случайную ориентацию
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NucleusOrientationParams {
+    pub model: NucleusOrientationModel,
+    #[serde(with = "units::angle")]
+    pub scatter: f64,
+    pub fraction: f64,
+}
+
+impl Default for NucleusOrientationParams {
+    fn default() -> Self {
+        NucleusOrientationParams { model: NucleusOrientationModel::Random, scatter: 5.0_f64.to_radians(), fraction: 1.0 }
+    }
+}
+
+//Накопление энергии, субзерна и подвижность границ
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecrystallizationParams {
+    pub num_sg: usize,
+    #[serde(with = "units::length")]
+    pub r0: f64,
+    pub alfa: f64,
+    #[serde(with = "units::surface_energy")]
+    pub egb: f64,
+    #[serde(with = "units::mobility")]
+    pub m0: f64,
+    #[serde(rename = "Q", with = "units::molar_energy")]
+    pub q: f64,
+    #[serde(with = "units::gas_constant")]
+    pub r: f64,
+    #[serde(with = "units::temperature")]
+    pub temp: f64,
+    pub stored_energy: StoredEnergyMode,
+    //Миграция границ рекристаллизованных зерен (рост за счет деформированных зерен)
+    pub grain_growth: bool,
+    //Зерна с радиусом меньше min_gr_size считаются поглощенными и удаляются из поликристалла
+    #[serde(with = "units::length")]
+    pub min_gr_size: f64,
+    //Допустимое относительное отклонение полного объема поликристалла от начального
+    pub volume_tolerance: f64,
+    pub nucleus_orientation: NucleusOrientationParams,
+}
+
+impl Default for RecrystallizationParams {
+    fn default() -> Self {
+        RecrystallizationParams {
+            num_sg: 2000,
+            r0: 2.0e-6,
+            alfa: 0.15,
+            egb: 0.324e-6,
+            m0: 1.5,
+            q: 196.0e3,
+            r: 8.31,
+            temp: 773.0,
+            stored_energy: StoredEnergyMode::Mean,
+            grain_growth: false,
+            min_gr_size: 1.0e-7,
+            volume_tolerance: 1.0e-6,
+            nucleus_orientation: NucleusOrientationParams::default(),
+        }
+    }
+}
+
+//Возврат (статический и динамический): уменьшение запасенной энергии и деформационного упрочнения
+//со скоростью rate0 exp(-Q/RT) + dynamic * sum|gamma_rate|. При нулевых rate0 и dynamic возврата нет
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoveryParams {
+    #[serde(with = "units::rate")]
+    pub rate0: f64,
+    #[serde(rename = "Q", with = "units::molar_energy")]
+    pub q: f64,
+    pub dynamic: f64,
+}
+
+impl Default for RecoveryParams {
+    fn default() -> Self {
+        RecoveryParams { rate0: 0.0, q: 196.0e3, dynamic: 0.0 }
+    }
+}
+
+//Закрепление границ частицами второй фазы (дисперсоидами): давление Зинера 3 f egb / 2 r
+//уменьшает движущие силы зарождения и миграции границ. Если задано precipitation_time, объемная
+//доля частиц растет от initial_fraction до fraction по закону JMAK с показателем precipitation_n
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PinningParams {
+    pub fraction: f64,
+    #[serde(with = "units::length")]
+    pub radius: f64,
+    pub initial_fraction: f64,
+    #[serde(with = "units::time")]
+    pub precipitation_time: f64,
+    pub precipitation_n: f64,
+}
+
+impl Default for PinningParams {
+    fn default() -> Self {
+        PinningParams { fraction: 0.0, radius: 50.0e-9, initial_fraction: 0.0, precipitation_time: 0.0, precipitation_n: 1.0 }
+    }
+}
+
+//Укрупнение субзерен: радиус субзерна меняется со скоростью M egb (1/R_cr - 1/R), где
+//M = m0 exp(-Q/RT), R_cr - критический радиус субзерен зерна (по сохранению их объема).
+//При m0 = 0 субзерна не укрупняются
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubgrainParams {
+    #[serde(with = "units::surface_energy")]
+    pub egb: f64,
+    #[serde(with = "units::mobility")]
+    pub m0: f64,
+    #[serde(rename = "Q", with = "units::molar_energy")]
+    pub q: f64,
+}
+
+impl Default for SubgrainParams {
+    fn default() -> Self {
+        SubgrainParams { egb: 0.1e-6, m0: 0.0, q: 196.0e3 }
+    }
+}
+
+//Вывод результатов расчета
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputParams {
+    //Шаг записи контрольной точки (0 - не записывать)
+    pub checkpoint_step: i64,
+    //Номера шагов, на которых записываются снимки состояния всех зерен
+    pub snapshot_steps: Vec<i64>,
+    //Число интервалов гистограммы радиусов субзерен в subgrains.dat и ее верхняя граница
+    //(0 - наибольший радиус субзерен на шаге вывода)
+    pub subgrain_bins: usize,
+    #[serde(with = "units::length")]
+    pub subgrain_r_max: f64,
+    //Номера шагов, на которых записывается распределение размеров зерен, и число его интервалов
+    pub grain_size_steps: Vec<i64>,
+    pub grain_size_bins: usize,
+}
+
+impl Default for OutputParams {
+    fn default() -> Self {
+        OutputParams {
+            checkpoint_step: 0,
+            snapshot_steps: Vec::new(),
+            subgrain_bins: 20,
+            subgrain_r_max: 0.0,
+            grain_size_steps: Vec::new(),
+            grain_size_bins: 20,
+        }
+    }
+}
+
+//Все параметры модели, сгруппированные по подсистемам.
+//Отсутствующие в файле значения берутся по умолчанию, см. реализации Default.
+//Размерные величины хранятся во внутренних единицах модели, см. units.rs.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Params {
-    params: HashMap<String, Value>,
+    pub time: TimeParams,
+    pub loading: LoadingParams,
+    pub polycrystal: PolycrystalParams,
+    pub elasticity: ElasticityParams,
+    pub slip: SlipParams,
+    pub hardening: HardeningParams,
+    pub recrystallization: RecrystallizationParams,
+    pub recovery: RecoveryParams,
+    pub subgrains: SubgrainParams,
+    pub pinning: PinningParams,
+    pub output: OutputParams,
+    //Стадии процесса. Если список пуст, процесс состоит из одной стадии деформирования
+    //с параметрами time, loading и recrystallization.temp
+    pub stages: Vec<StageParams>,
+    //Seed генератора случайных чисел. Если не задан, выбирается случайно и сохраняется в параметрах расчета
+    pub seed: Option<u64>,
 }
 
 impl Params {
     pub fn new() -> Self {
-        Params {
-            params: HashMap::new(),
-        }
+        Params::default()
     }
+}
 
-    pub fn set_hash_map(&mut self, data: HashMap<String, Value>) {
-        self.params = data;
+//Список всех ошибок конфигурации, найденных до начала расчета
+#[derive(Debug)]
+pub struct ConfigError {
+    problems: Vec<String>,
+}
+
+impl ConfigError {
+    pub fn new(problems: Vec<String>) -> Self {
+        ConfigError { problems }
     }
 
-    pub fn get_f64(&self, key: &str) -> f64 {
-        if let Some(value) = self.params.get(key) {
-            if let Some(number) = value.as_f64() {
-                return number;
-            } else {
-                println!("Значение ключа {} не является f64", key)
-            }
-        } else {
-            println!("Ключ {} не найден", key)
-        }
-        0.0
-    }
-    pub fn get_i64(&self, key: &str) -> i64 {
-        if let Some(value) = self.params.get(key) {
-            if let Some(number) = value.as_i64() {
-                return number;
-            } else {
-                println!("Значение ключа {} не является i64", key)
-            }
-        } else {
-            println!("Ключ {} не найден", key)
+    pub fn get_problems(&self) -> &[String] {
+        &self.problems
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Ошибки в параметрах модели ({}):", self.problems.len())?;
+        for problem in self.problems.iter() {
+            writeln!(f, "  - {}", problem)?;
         }
-        0
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl std::error::Error for ConfigError {}
@@ -1,27 +1,357 @@
 #![allow(dead_code)]
-use serde_json::{Value, from_str};
-use std::{
-    collections::HashMap, 
-    fs, 
-    path::PathBuf};
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
 
-use crate::consts::FILE_INPUT_PATH;
-use super::components::Params;
+use super::components::{ConfigError, Params, StageKind, StageParams};
 
 
-pub fn from_file(params: &mut Params) {
+pub fn from_file(path: &Path) -> Result<Params, ConfigError> {
+    from_value(&read_json(path)?)
+}
+
+//Содержимое файла параметров без проверки, например для внесения изменений из командной строки
+pub fn read_json(path: &Path) -> Result<Value, ConfigError> {
     // Прочитать JSON файл и считать его содержимое в виде строки
-    let json_string = fs::read_to_string(PathBuf::from(FILE_INPUT_PATH).join("param.json")).expect("Ошибка открытия файла param.json");
+    let json_string = fs::read_to_string(path)
+        .map_err(|err| ConfigError::new(vec![format!("Ошибка открытия файла {}: {}", path.display(), err)]))?;
+    serde_json::from_str(&json_string)
+        .map_err(|err| ConfigError::new(vec![format!("Ошибка перевода строки json в значения: {}", err)]))
+}
 
+pub fn from_json_str(json_string: &str) -> Result<Params, ConfigError> {
     // Десериализовать JSON-строку в serde_json::Value
-    let json_value: Value = from_str(&json_string).expect("Ошибка перевода строки json в значения");
-
-    // Преобразовать Value в HashMap, если JSON представляет собой объект
-    if let Some(map) = json_value.as_object() {
-        let hashmap: HashMap<String, Value> = map.iter()
-            .map(|(key, value)| (key.clone(), value.clone()))
-            .collect();
-        params.set_hash_map(hashmap);
-        
-    } 
-}
\ No newline at end of file
+    let json_value: Value = serde_json::from_str(json_string)
+        .map_err(|err| ConfigError::new(vec![format!("Ошибка перевода строки json в значения: {}", err)]))?;
+    from_value(&json_value)
+}
+
+//Проверяет структуру, типы и допустимые диапазоны всех параметров.
+//Все найденные ошибки собираются в один отчет: ошибочные значения отбрасываются,
+//чтобы проверка диапазонов остальных параметров тоже была выполнена.
+pub fn from_value(json_value: &Value) -> Result<Params, ConfigError> {
+    let mut schema = serde_json::to_value(Params::default()).expect("Ошибка сериализации параметров по умолчанию");
+    //Элементы списков проверяются по первому элементу схемы
+    schema["stages"] = Value::Array(vec![
+        serde_json::to_value(StageParams::default()).expect("Ошибка сериализации параметров по умолчанию"),
+    ]);
+    let mut problems = Vec::new();
+    let mut checked_value = json_value.clone();
+    if !check_schema(&mut checked_value, &schema, "", &mut problems) {
+        return Err(ConfigError::new(problems));
+    }
+
+    let params: Params = serde_json::from_value(checked_value)
+        .map_err(|err| ConfigError::new(vec![err.to_string()]))?;
+    problems.extend(validate(&params));
+    if problems.is_empty() {
+        Ok(params)
+    } else {
+        Err(ConfigError::new(problems))
+    }
+}
+
+//Сравнение с параметрами по умолчанию: неизвестные ключи (опечатки) и несовпадение типов.
+//Возвращает false, если значение не подходит и должно быть удалено из конфигурации.
+fn check_schema(value: &mut Value, schema: &Value, prefix: &str, problems: &mut Vec<String>) -> bool {
+    match (&mut *value, schema) {
+        (Value::Object(map), Value::Object(schema_map)) => {
+            map.retain(|key, item| {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                match schema_map.get(key) {
+                    Some(schema_item) => check_schema(item, schema_item, &path, problems),
+                    None => {
+                        let known: Vec<&str> = schema_map.keys().map(|k| k.as_str()).collect();
+                        problems.push(format!("неизвестный параметр \"{}\" (допустимые: {})", path, known.join(", ")));
+                        false
+                    }
+                }
+            });
+            true
+        }
+        (Value::Array(items), Value::Array(schema_items)) if !schema_items.is_empty() => {
+            let mut all_valid = true;
+            for (index, item) in items.iter_mut().enumerate() {
+                all_valid &= check_schema(item, &schema_items[0], &format!("{}[{}]", prefix, index), problems);
+            }
+            all_valid
+        }
+        (_, Value::Object(_)) => {
+            problems.push(format!("\"{}\" должен быть объектом", prefix));
+            false
+        }
+        (Value::Number(number), Value::Number(schema_number))
+            if (schema_number.is_u64() || schema_number.is_i64()) && number.as_i64().is_none() =>
+        {
+            problems.push(format!("\"{}\" должен быть целым числом, получено {}", prefix, number));
+            false
+        }
+        (Value::Number(_), Value::Number(_)) => true,
+        //Размерная величина, заданная строкой с единицей измерения
+        (Value::String(_), Value::Number(schema_number)) if schema_number.is_f64() => {
+            match probe_value(prefix, value) {
+                Ok(()) => true,
+                Err(err) => {
+                    problems.push(format!("\"{}\": {}", prefix, err));
+                    false
+                }
+            }
+        }
+        (value, Value::Number(_)) => {
+            problems.push(format!("\"{}\" должен быть числом, получено {}", prefix, value));
+            false
+        }
+        _ => true,
+    }
+}
+
+//Пробное чтение одного параметра, чтобы сообщить об ошибке в единицах измерения
+fn probe_value(path: &str, value: &Value) -> Result<(), serde_json::Error> {
+    let mut probe = value.clone();
+    for part in path.rsplit('.') {
+        //Элемент списка "stages[0]" проверяется как единственный элемент списка
+        let (key, item) = match part.split_once('[') {
+            Some((key, _)) => (key, Value::Array(vec![probe])),
+            None => (part, probe),
+        };
+        let mut map = Map::new();
+        map.insert(key.to_string(), item);
+        probe = Value::Object(map);
+    }
+    serde_json::from_value::<Params>(probe).map(|_| ())
+}
+
+//Проверка допустимых диапазонов значений
+pub fn validate(params: &Params) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut positive = |name: &str, value: f64| {
+        if !(value > 0.0 && value.is_finite()) {
+            problems.push(format!("\"{}\" должен быть > 0, получено {}", name, value));
+        }
+    };
+    positive("time.dt", params.time.dt);
+    positive("loading.strain_rate", params.loading.strain_rate);
+    positive("polycrystal.gr_size", params.polycrystal.gr_size);
+    //Зерно с нулевым радиусом никогда не считалось бы поглощенным
+    positive("recrystallization.min_gr_size", params.recrystallization.min_gr_size);
+    positive("elasticity.c11", params.elasticity.c11);
+    positive("elasticity.c12", params.elasticity.c12);
+    positive("elasticity.c44", params.elasticity.c44);
+    positive("elasticity.koef", params.elasticity.koef);
+    positive("slip.gamma_0", params.slip.gamma_0);
+    positive("slip.m", params.slip.m);
+    positive("slip.tau_c", params.slip.tau_c);
+    positive("slip.b", params.slip.b);
+    positive("hardening.tau_sat", params.hardening.tau_sat);
+    positive("recrystallization.r0", params.recrystallization.r0);
+    positive("recrystallization.r", params.recrystallization.r);
+    positive("recrystallization.temp", params.recrystallization.temp);
+    positive("recrystallization.volume_tolerance", params.recrystallization.volume_tolerance);
+    positive("subgrains.egb", params.subgrains.egb);
+    positive("pinning.radius", params.pinning.radius);
+    positive("pinning.precipitation_n", params.pinning.precipitation_n);
+
+    for (index, stage) in params.stages.iter().enumerate() {
+        let name = format!("stages[{}]", index);
+        positive(&format!("{}.duration", name), stage.duration);
+        positive(&format!("{}.dt", name), stage.dt);
+        positive(&format!("{}.temp", name), stage.temp);
+        if stage.kind == StageKind::Deformation {
+            positive(&format!("{}.strain_rate", name), stage.strain_rate);
+        }
+    }
+    for (index, stage) in params.stages.iter().enumerate() {
+        if stage.dt > 0.0 && stage.get_steps() < 1 {
+            problems.push(format!(
+                "\"stages[{}].duration\" ({}) должна быть не меньше шага по времени ({})",
+                index, stage.duration, stage.dt
+            ));
+        }
+    }
+    if params.time.steps_num < 0 {
+        problems.push(format!("\"time.steps_num\" должен быть >= 0, получено {}", params.time.steps_num));
+    }
+    if params.time.write_step < 1 {
+        problems.push(format!("\"time.write_step\" должен быть >= 1, получено {}", params.time.write_step));
+    }
+    if params.output.checkpoint_step < 0 {
+        problems.push(format!("\"output.checkpoint_step\" должен быть >= 0, получено {}", params.output.checkpoint_step));
+    }
+    let total_steps = get_total_steps(params);
+    if params.output.snapshot_steps.iter().any(|&step| step < 0 || step > total_steps) {
+        problems.push(format!(
+            "\"output.snapshot_steps\" должны быть в диапазоне 0..={}, получено {:?}",
+            total_steps, params.output.snapshot_steps
+        ));
+    }
+    if params.polycrystal.grain_num < 1 {
+        problems.push("\"polycrystal.grain_num\" должен быть >= 1".to_string());
+    }
+    for (name, value) in [("pinning.fraction", params.pinning.fraction), ("pinning.initial_fraction", params.pinning.initial_fraction)] {
+        if !(0.0..1.0).contains(&value) {
+            problems.push(format!("\"{}\" должна быть в диапазоне [0, 1), получено {}", name, value));
+        }
+    }
+    let fraction = params.recrystallization.nucleus_orientation.fraction;
+    if !(0.0..=1.0).contains(&fraction) {
+        problems.push(format!(
+            "\"recrystallization.nucleus_orientation.fraction\" должна быть в диапазоне 0..=1, получено {}",
+            fraction
+        ));
+    }
+    if params.output.grain_size_steps.iter().any(|&step| step < 0 || step > total_steps) {
+        problems.push(format!(
+            "\"output.grain_size_steps\" должны быть в диапазоне 0..={}, получено {:?}",
+            total_steps, params.output.grain_size_steps
+        ));
+    }
+    if params.output.grain_size_bins < 1 {
+        problems.push("\"output.grain_size_bins\" должен быть >= 1".to_string());
+    }
+    if params.output.subgrain_bins < 1 {
+        problems.push("\"output.subgrain_bins\" должен быть >= 1".to_string());
+    }
+    if !(params.output.subgrain_r_max >= 0.0 && params.output.subgrain_r_max.is_finite()) {
+        problems.push(format!("\"output.subgrain_r_max\" должен быть >= 0, получено {}", params.output.subgrain_r_max));
+    }
+    if params.recrystallization.num_sg < 1 {
+        problems.push("\"recrystallization.num_sg\" должен быть >= 1".to_string());
+    }
+    if params.polycrystal.std_dev < 0.0 {
+        problems.push(format!("\"polycrystal.std_dev\" должен быть >= 0, получено {}", params.polycrystal.std_dev));
+    }
+    for (name, value) in [
+        ("recovery.rate0", params.recovery.rate0),
+        ("recovery.Q", params.recovery.q),
+        ("recovery.dynamic", params.recovery.dynamic),
+        ("subgrains.m0", params.subgrains.m0),
+        ("subgrains.Q", params.subgrains.q),
+        ("recrystallization.nucleus_orientation.scatter", params.recrystallization.nucleus_orientation.scatter),
+        ("pinning.precipitation_time", params.pinning.precipitation_time),
+    ] {
+        if !(value >= 0.0 && value.is_finite()) {
+            problems.push(format!("\"{}\" должен быть >= 0, получено {}", name, value));
+        }
+    }
+    if params.slip.k_y < 0.0 {
+        problems.push(format!("\"slip.k_y\" должен быть >= 0, получено {}", params.slip.k_y));
+    }
+    if params.hardening.tau_sat <= params.slip.tau_c {
+        problems.push(format!(
+            "\"hardening.tau_sat\" ({}) должен быть больше \"slip.tau_c\" ({})",
+            params.hardening.tau_sat, params.slip.tau_c
+        ));
+    }
+    problems
+}
+
+//Стадии процесса. Без заданных стадий - одна стадия деформирования с параметрами time, loading
+//и recrystallization.temp, как в расчетах без стадий
+pub fn get_stages(params: &Params) -> Vec<StageParams> {
+    if !params.stages.is_empty() {
+        return params.stages.clone();
+    }
+    vec![StageParams {
+        name: "deformation".to_string(),
+        kind: StageKind::Deformation,
+        duration: params.time.dt * params.time.steps_num as f64,
+        dt: params.time.dt,
+        temp: params.recrystallization.temp,
+        strain_rate: params.loading.strain_rate,
+        relax_stress: true,
+    }]
+}
+
+//Полное число шагов всех стадий
+pub fn get_total_steps(params: &Params) -> i64 {
+    if params.stages.is_empty() {
+        return params.time.steps_num;
+    }
+    params.stages.iter().map(|stage| stage.get_steps()).sum()
+}
+
+//Значение параметра по составному ключу вида "slip.tau_c"
+pub fn get_value(params: &Params, key: &str) -> Option<Value> {
+    let mut value = serde_json::to_value(params).ok()?;
+    for part in key.split('.') {
+        value = value.get(part)?.clone();
+    }
+    Some(value)
+}
+
+//Изменение параметра по составному ключу с повторной проверкой всех параметров
+pub fn set_value(params: &mut Params, key: &str, new_value: Value) -> Result<(), ConfigError> {
+    let mut value = serde_json::to_value(&*params).expect("Ошибка сериализации параметров");
+    insert_value(&mut value, key, new_value).map_err(|err| ConfigError::new(vec![err]))?;
+    *params = from_value(&value)?;
+    Ok(())
+}
+
+//Запись значения по составному ключу в JSON-представление параметров (без проверки)
+pub fn insert_value(value: &mut Value, key: &str, new_value: Value) -> Result<(), String> {
+    let mut target = value;
+    let parts: Vec<&str> = key.split('.').collect();
+    for part in parts[..parts.len() - 1].iter() {
+        if !target.is_object() {
+            break;
+        }
+        target = target
+            .as_object_mut()
+            .unwrap()
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    match target.as_object_mut() {
+        Some(map) => {
+            map.insert(parts[parts.len() - 1].to_string(), new_value);
+            Ok(())
+        }
+        None => Err(format!("\"{}\" не является группой параметров", key)),
+    }
+}
+
+//Небольшой поликристалл для тестов: число зерен и субзерен, seed и число шагов.
+//Тот же построитель для интеграционных тестов - tests/common/mod.rs
+#[cfg(test)]
+pub struct SmallParams {
+    params: Params,
+}
+
+#[cfg(test)]
+impl SmallParams {
+    pub fn new(grain_num: usize, num_sg: usize) -> Self {
+        let mut params = Params::new();
+        set_value(&mut params, "polycrystal.grain_num", grain_num.into()).unwrap();
+        set_value(&mut params, "recrystallization.num_sg", num_sg.into()).unwrap();
+        SmallParams { params }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        set_value(&mut self.params, "seed", seed.into()).unwrap();
+        self
+    }
+
+    pub fn steps(mut self, steps_num: i64, write_step: i64) -> Self {
+        set_value(&mut self.params, "time.steps_num", steps_num.into()).unwrap();
+        set_value(&mut self.params, "time.write_step", write_step.into()).unwrap();
+        self
+    }
+
+    pub fn build(self) -> Params {
+        self.params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_min_grain_size_is_rejected() {
+        let mut params = Params::new();
+        assert!(validate(&params).is_empty());
+        params.recrystallization.min_gr_size = 0.0;
+        let problems = validate(&params);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("recrystallization.min_gr_size"));
+    }
+}
@@ -0,0 +1,214 @@
+#![allow(dead_code)]
+//Единицы измерения параметров модели.
+//Во входном файле величина задается числом в единицах СИ (Па, м, Дж/моль, ...) или строкой
+//с единицей измерения ("106.8 GPa", "400 um", "196 kJ/mol"). При чтении все величины
+//переводятся во внутреннюю систему единиц модели, при записи - обратно в СИ
+//(или строкой во внутренних единицах, если число в СИ не читается обратно точно):
+//  напряжения и плотность энергии - МПа (= МДж/м^3), длина - м, время - с, температура - К,
+//  энергия активации - Дж/моль, энергия границ - МДж/м^2, подвижность границ - м^4/(МДж*с),
+//  углы - рад.
+use serde_json::Value;
+
+use crate::consts::{CENTI, GIGA, KILO, MEGA, MICRO, MILLI, NANO};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dimension {
+    Stress,
+    Length,
+    Time,
+    Rate,
+    Temperature,
+    MolarEnergy,
+    GasConstant,
+    SurfaceEnergy,
+    Mobility,
+    Angle,
+}
+
+impl Dimension {
+    //Множитель перевода из СИ во внутренние единицы модели
+    fn internal_factor(self) -> f64 {
+        match self {
+            Dimension::Stress => 1.0 / MEGA,
+            Dimension::SurfaceEnergy => 1.0 / MEGA,
+            Dimension::Mobility => MEGA,
+            _ => 1.0,
+        }
+    }
+
+    //Допустимые единицы измерения и множители перевода в СИ
+    fn units(self) -> &'static [(&'static str, f64)] {
+        match self {
+            Dimension::Stress => &[("Pa", 1.0), ("kPa", KILO), ("MPa", MEGA), ("GPa", GIGA)],
+            Dimension::Length => &[
+                ("m", 1.0),
+                ("cm", CENTI),
+                ("mm", MILLI),
+                ("um", MICRO),
+                ("µm", MICRO),
+                ("μm", MICRO),
+                ("nm", NANO),
+            ],
+            Dimension::Time => &[("s", 1.0), ("ms", MILLI), ("us", MICRO), ("min", 60.0), ("h", 3600.0)],
+            Dimension::Rate => &[("1/s", 1.0), ("s^-1", 1.0), ("1/ms", 1.0 / MILLI)],
+            Dimension::Temperature => &[("K", 1.0)],
+            Dimension::MolarEnergy => &[("J/mol", 1.0), ("kJ/mol", KILO)],
+            Dimension::GasConstant => &[("J/(molK)", 1.0), ("J/mol/K", 1.0), ("J/(Kmol)", 1.0)],
+            Dimension::SurfaceEnergy => &[
+                ("J/m^2", 1.0),
+                ("J/m2", 1.0),
+                ("mJ/m^2", MILLI),
+                ("mJ/m2", MILLI),
+                ("MJ/m^2", MEGA),
+                ("MJ/m2", MEGA),
+            ],
+            Dimension::Mobility => &[("m^4/(Js)", 1.0), ("m4/(Js)", 1.0), ("m^4/J/s", 1.0), ("m^4/(MJs)", MICRO)],
+            Dimension::Angle => &[("rad", 1.0), ("deg", std::f64::consts::PI / 180.0), ("°", std::f64::consts::PI / 180.0)],
+        }
+    }
+
+    //Единица измерения, в которой величина хранится в модели
+    fn internal_unit(self) -> &'static str {
+        match self {
+            Dimension::Stress => "MPa",
+            Dimension::SurfaceEnergy => "MJ/m^2",
+            Dimension::Mobility => "m^4/(MJs)",
+            _ => self.units()[0].0,
+        }
+    }
+}
+
+//Перевод строки вида "106.8 GPa" во внутренние единицы
+pub fn parse_quantity(text: &str, dimension: Dimension) -> Result<f64, String> {
+    let text = text.trim();
+    let split = text
+        .char_indices()
+        .find(|&(index, c)| {
+            //Экспонента числа (1e-3) не считается началом единицы измерения
+            !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'
+                || ((c == 'e' || c == 'E') && index > 0 && text[index + 1..].starts_with(|n: char| n.is_ascii_digit() || n == '-' || n == '+')))
+        })
+        .map_or(text.len(), |(index, _)| index);
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("не удалось прочитать число в \"{}\"", text))?;
+    //Пробелы, точки и звездочки внутри единицы не учитываются: "J/(mol K)" = "J/(mol*K)" = "J/(molK)"
+    let unit: String = unit.chars().filter(|c| !c.is_whitespace() && *c != '*' && *c != '·').collect();
+
+    if dimension == Dimension::Temperature && (unit == "C" || unit == "°C") {
+        return Ok(number + 273.15);
+    }
+    if unit.is_empty() {
+        return Ok(number * dimension.internal_factor());
+    }
+    match dimension.units().iter().find(|(name, _)| *name == unit) {
+        //Величина во внутренних единицах читается без пересчета
+        Some((_, factor)) if factor * dimension.internal_factor() == 1.0 => Ok(number),
+        Some((_, factor)) => Ok(number * factor * dimension.internal_factor()),
+        None => {
+            let known: Vec<&str> = dimension.units().iter().map(|(name, _)| *name).collect();
+            Err(format!("неизвестная единица \"{}\" в \"{}\" (допустимые: {})", unit, text, known.join(", ")))
+        }
+    }
+}
+
+pub fn from_json_value(value: &Value, dimension: Dimension) -> Result<f64, String> {
+    match value {
+        Value::Number(number) => Ok(number.as_f64().unwrap() * dimension.internal_factor()),
+        Value::String(text) => parse_quantity(text, dimension),
+        _ => Err(format!("ожидалось число или строка с единицей измерения, получено {}", value)),
+    }
+}
+
+pub fn to_si(value: f64, dimension: Dimension) -> f64 {
+    value / dimension.internal_factor()
+}
+
+//Значение для записи: число в СИ, если при чтении из него получается в точности value
+//(перевод через множитель 1e-6 неточен, поэтому проверяются и соседние числа),
+//иначе строка во внутренних единицах, которая читается без пересчета
+pub fn to_json_value(value: f64, dimension: Dimension) -> Value {
+    let si = to_si(value, dimension);
+    if !si.is_finite() {
+        return Value::from(si);
+    }
+    match [si, si.next_up(), si.next_down()].into_iter().find(|si| si * dimension.internal_factor() == value) {
+        Some(si) => Value::from(si),
+        None => Value::from(format!("{:?} {}", value, dimension.internal_unit())),
+    }
+}
+
+//Модули для #[serde(with = "...")]: чтение с переводом во внутренние единицы, запись в СИ
+macro_rules! quantity_serde {
+    ($name:ident, $dimension:expr) => {
+        pub mod $name {
+            use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+            use serde_json::Value;
+
+            pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+                super::to_json_value(*value, $dimension).serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+                let value = Value::deserialize(deserializer)?;
+                super::from_json_value(&value, $dimension).map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+quantity_serde!(stress, super::Dimension::Stress);
+quantity_serde!(length, super::Dimension::Length);
+quantity_serde!(time, super::Dimension::Time);
+quantity_serde!(rate, super::Dimension::Rate);
+quantity_serde!(temperature, super::Dimension::Temperature);
+quantity_serde!(molar_energy, super::Dimension::MolarEnergy);
+quantity_serde!(gas_constant, super::Dimension::GasConstant);
+quantity_serde!(surface_energy, super::Dimension::SurfaceEnergy);
+quantity_serde!(mobility, super::Dimension::Mobility);
+quantity_serde!(angle, super::Dimension::Angle);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn quantities_are_parsed_into_internal_units() {
+        assert!((parse_quantity("106.8 GPa", Dimension::Stress).unwrap() - 106800.0).abs() < 1.0e-9);
+        assert_eq!(parse_quantity("400 um", Dimension::Length).unwrap(), 400.0 * MICRO);
+        assert_eq!(parse_quantity("196 kJ/mol", Dimension::MolarEnergy).unwrap(), 196000.0);
+        assert_eq!(parse_quantity("8.314 J/(mol K)", Dimension::GasConstant).unwrap(), 8.314);
+        assert_eq!(parse_quantity("1e-2 1/s", Dimension::Rate).unwrap(), 1.0e-2);
+        assert_eq!(parse_quantity("20 °C", Dimension::Temperature).unwrap(), 293.15);
+        assert!((parse_quantity("0.5 J/m^2", Dimension::SurfaceEnergy).unwrap() - 0.5e-6).abs() < 1.0e-20);
+        assert!((parse_quantity("1.5e-6 m^4/(J s)", Dimension::Mobility).unwrap() - 1.5).abs() < 1.0e-12);
+        assert!(parse_quantity("10 kg", Dimension::Stress).is_err());
+        assert!(parse_quantity("abc", Dimension::Stress).is_err());
+    }
+
+    #[test]
+    fn serialized_quantities_are_read_back_exactly() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let dimensions = [Dimension::Stress, Dimension::SurfaceEnergy, Dimension::Mobility, Dimension::Length, Dimension::Angle];
+        for dimension in dimensions {
+            for _ in 0..20000 {
+                let value = rng.gen_range(0.5..2.0) * 10.0_f64.powf(rng.gen_range(-12.0..12.0));
+                let written = serde_json::to_string(&to_json_value(value, dimension)).unwrap();
+                let read = from_json_value(&serde_json::from_str(&written).unwrap(), dimension).unwrap();
+                assert_eq!(read.to_bits(), value.to_bits(), "{:?}: {} записано как {}", dimension, value, written);
+            }
+        }
+    }
+
+    #[test]
+    fn quantities_from_input_are_written_as_si_numbers() {
+        for (text, dimension) in [("106.8 GPa", Dimension::Stress), ("70 MPa", Dimension::Stress), ("0.5 J/m^2", Dimension::SurfaceEnergy), ("1.5e-6 m^4/(J s)", Dimension::Mobility)] {
+            let value = parse_quantity(text, dimension).unwrap();
+            assert!(to_json_value(value, dimension).is_number(), "{}", text);
+        }
+    }
+}
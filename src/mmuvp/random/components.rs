@@ -0,0 +1,23 @@
+#![allow(dead_code)]
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+//Генератор случайных чисел зерна: общий seed расчета и собственный поток по номеру сущности,
+//поэтому последовательность чисел зерна не зависит от порядка обхода зерен и числа потоков
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RngComponent {
+    rng: ChaCha8Rng,
+}
+
+impl RngComponent {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        RngComponent { rng }
+    }
+
+    pub fn get_rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.rng
+    }
+}
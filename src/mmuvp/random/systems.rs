@@ -0,0 +1,6 @@
+#![allow(dead_code)]
+
+//Seed для расчета без заданного в параметрах значения
+pub fn gen_seed() -> u64 {
+    rand::random()
+}
@@ -0,0 +1,20 @@
+#![allow(dead_code)]
+use serde::{Deserialize, Serialize};
+
+//Скорость возврата зерна (статический и динамический), 1/с
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecoveryRateComponent{
+    value: f64,
+}
+
+impl RecoveryRateComponent{
+    pub fn new()->Self{
+        RecoveryRateComponent{value: 0.0}
+    }
+    pub fn set_value(&mut self, value:f64){
+        self.value = value;
+    }
+    pub fn get_value(&self)->f64{
+        self.value
+    }
+}
@@ -0,0 +1,100 @@
+#![allow(dead_code)]
+//Возврат: термически активируемое уменьшение запасенной энергии и деформационного упрочнения.
+//Упрочнение каждой системы скольжения (превышение tau_c над значением с поправкой Холла-Петча)
+//хранится отдельно, поэтому пересчет Холла-Петча при изменении размера зерна его не затрагивает.
+//Оба слагаемых убывают с одной скоростью r = r0 exp(-Q/RT) + k_d sum|gamma_rate|,
+//интегрирование точное по шагу: множитель exp(-r dt)
+use std::collections::HashMap;
+
+use crate::mmuvp::{
+    entity::CrystalEntity,
+    recrystallization::components::AccumEnergyComponent,
+    slide_system::{
+        components::{GammaRateComponent, TauComponent, TauRateComponent},
+        systems::get_tauc,
+    },
+};
+
+use super::components::*;
+
+pub fn calc_recovery_rate(
+    recovery_rate_map: &mut HashMap<CrystalEntity, RecoveryRateComponent>,
+    gamma_rate_map: &HashMap<CrystalEntity, GammaRateComponent>,
+    rate0: f64,
+    q: f64,
+    r: f64,
+    dynamic: f64,
+    temp: f64,
+) {
+    let static_rate = rate0 * (-q / (r * temp)).exp();
+    for (entity, recovery_rate_component) in recovery_rate_map.iter_mut() {
+        if let Some(gamma_rate_component) = gamma_rate_map.get(entity) {
+            let mut slip_rate = 0.0;
+            for index in 0..24 {
+                slip_rate += gamma_rate_component.get_values(index).expect("Ошибка извлечения gamma_rate").abs();
+            }
+            recovery_rate_component.set_value(static_rate + dynamic * slip_rate);
+        } else {
+            panic!("Ошибка поиска компонента gamma_rate")
+        }
+    }
+}
+
+//Накопление упрочнения за шаг и его возврат. Вызывается после calc_tauc с той же скоростью tau_c_rate
+pub fn calc_tauc_recovery(
+    tau_c_map: &mut HashMap<CrystalEntity, TauComponent>,
+    tau_hard_map: &mut HashMap<CrystalEntity, TauComponent>,
+    tau_c_rate_map: &HashMap<CrystalEntity, TauRateComponent>,
+    recovery_rate_map: &HashMap<CrystalEntity, RecoveryRateComponent>,
+    dt: f64,
+) {
+    for (entity, tau_hard_component) in tau_hard_map.iter_mut() {
+        let tauc_rate_component = tau_c_rate_map.get(entity).expect("Ошибка поиска компонента tau_c_rate");
+        let tauc_component = tau_c_map.get_mut(entity).expect("Ошибка поиска компонента tau_c");
+        let rate = recovery_rate_map.get(entity).expect("Ошибка поиска компонента recovery_rate").get_value();
+        let factor = (-rate * dt).exp();
+        for index in 0..24 {
+            let tauc_rate = tauc_rate_component.get_values(index).expect("Ошибка извлечения tauc_rate");
+            let tau_hard = tau_hard_component.get_values(index).expect("Ошибка извлечения tau_hard") + tauc_rate * dt;
+            if rate > 0.0 {
+                let tauc = tauc_component.get_values(index).expect("Ошибка извлечения tauc");
+                let recovered = tau_hard * (1.0 - factor);
+                tauc_component.set_values(index, tauc - recovered);
+                tau_hard_component.set_values(index, tau_hard - recovered);
+            } else {
+                tau_hard_component.set_values(index, tau_hard);
+            }
+        }
+    }
+}
+
+pub fn calc_accum_energy_recovery(
+    est_map: &mut HashMap<CrystalEntity, AccumEnergyComponent>,
+    recovery_rate_map: &HashMap<CrystalEntity, RecoveryRateComponent>,
+    dt: f64,
+) {
+    for (entity, est_component) in est_map.iter_mut() {
+        let rate = recovery_rate_map.get(entity).expect("Ошибка поиска компонента recovery_rate").get_value();
+        if rate > 0.0 {
+            est_component.set_value(est_component.get_value() * (-rate * dt).exp());
+        }
+    }
+}
+
+//Новое рекристаллизованное зерно свободно от дислокаций: запасенная энергия и упрочнение
+//обнуляются, критические напряжения равны начальным с поправкой Холла-Петча
+pub fn reset_recrystallized_grain(
+    est_component: &mut AccumEnergyComponent,
+    tau_c_component: &mut TauComponent,
+    tau_hard_component: &mut TauComponent,
+    tauc: f64,
+    b: f64,
+    k_y: f64,
+    d_g: f64,
+) {
+    est_component.set_value(0.0);
+    get_tauc(tau_c_component, tauc, b, k_y, d_g);
+    for index in 0..24 {
+        tau_hard_component.set_values(index, 0.0);
+    }
+}
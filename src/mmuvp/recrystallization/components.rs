@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+use serde::{Deserialize, Serialize};
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StatusRecrystComponent{
     status:bool,
 }
@@ -14,6 +16,7 @@ impl StatusRecrystComponent {
         self.status = value
     }
 }
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GrainSizeComponent{
     value:f64
 }
@@ -28,6 +31,7 @@ impl GrainSizeComponent{
         self.value
     }
 }
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AccumEnergyComponent{
     value: f64,
 }
@@ -43,6 +47,7 @@ impl AccumEnergyComponent{
         self.value
     }
 }
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AccumEnergyRateComponent{
     value: f64,
 }
@@ -59,6 +64,7 @@ impl AccumEnergyRateComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SubGrainsComponent{
     vector: Vec<f64>,
 }
@@ -86,6 +92,7 @@ impl SubGrainsComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DriveForceRecrComponent{
     vector: Vec<f64>
 }
@@ -113,6 +120,7 @@ impl DriveForceRecrComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DriveForceRecrCrystComponent{
     value: f64
 }
@@ -128,6 +136,7 @@ impl DriveForceRecrCrystComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FacetMobilityComponent{
     value: f64,
 }
@@ -144,6 +153,7 @@ impl FacetMobilityComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VelocityFacetComponent{
     value: f64
 }
@@ -158,33 +168,38 @@ impl VelocityFacetComponent{
         self.value
     }
 }
-
-pub struct NewGrainsComponent{
-    vector: Vec<f64>,
+//История объемной доли рекристаллизованных зерен: пары (время, доля) на шагах вывода
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecrystHistoryComponent{
+    values: Vec<(f64, f64)>,
+}
+impl RecrystHistoryComponent{
+    pub fn new()->Self{
+        RecrystHistoryComponent{values: Vec::new()}
+    }
+    pub fn push_value(&mut self, time: f64, fraction: f64){
+        self.values.push((time, fraction));
+    }
+    pub fn get_values(&self)->&[(f64, f64)]{
+        &self.values
+    }
+}
+//Распределение размеров зерен: среднее и стандартное отклонение радиуса и параметры
+//логнормального распределения ln r ~ N(mu, sigma), оцененные методом максимального правдоподобия
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LognormalFit{
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub mu: f64,
+    pub sigma: f64,
+}
+//Параметры уравнения Джонсона-Мела-Аврами-Колмогорова X = 1 - exp(-k t^n)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JmakFit{
+    pub n: f64,
+    pub k: f64,
+    //Коэффициент детерминации линейной регрессии ln(-ln(1-X)) от ln t
+    pub r2: f64,
+    pub points: usize,
 }
-impl NewGrainsComponent{
-    pub fn new()->Self {
-        NewGrainsComponent{vector: Vec::new()}
-    }
-    pub fn set_value(&mut self, index: usize, value: f64) {
-        if index < self.vector.len() {
-            self.vector[index] = value;
-        }
-    }
-    pub fn push_value(&mut self, value:f64){
-        self.vector.push(value);
-    }
-    pub fn get_value(&self, index: usize) -> Option<f64> {
-        if index < self.vector.len() {
-            Some(self.vector[index])
-        } else {
-            None
-        }
-    }
-    pub fn len(&self) -> usize {
-        self.vector.len()
-    }
-    pub fn clear(&mut self){
-        self.vector.clear();
-    }
-}
\ No newline at end of file
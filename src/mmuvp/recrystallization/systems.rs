@@ -1,10 +1,16 @@
 #![allow(dead_code)]
 
-use std::{collections::HashMap, fs::OpenOptions, path::PathBuf, io::{BufWriter,Write}};
+use std::{collections::HashMap, fs::{File, OpenOptions}, path::Path, io::{self, BufWriter, Write}};
 
 use crate::{
-    consts::{MEGA, FILE_OUTPUT_PATH},
-    mmuvp::{elasticity::components::*, entity::CrystalEntity}, TauComponent,
+    consts::SUBGRAIN_REMOVED_R,
+    mmuvp::{
+        elasticity::components::*,
+        entity::{sorted_entities, CrystalEntity},
+        events::components::{EventQueueComponent, GrainEvent},
+        random::components::RngComponent,
+        slide_system::components::TauComponent,
+    },
 };
 
 use super::components::*;
@@ -20,7 +26,7 @@ pub fn calc_accum_energy_rate(
     for (entity, est_rate_component) in est_rate_map.iter_mut() {
         if let Some(sigma_component) = sigma_map.get(entity) {
             if let Some(din_component) = din_map.get(entity) {
-                let sigma_tensor = sigma_component.get_tensor() * MEGA;
+                let sigma_tensor = sigma_component.get_tensor();
                 let din_tensor = din_component.get_tensor();
                 let value = alfa * (sigma_tensor.dot(&din_tensor));
                 est_rate_component.set_value(value);
@@ -50,23 +56,31 @@ pub fn calc_accum_energy(
     }
 }
 
-pub fn calc_mean_accum_energy(est_map: &HashMap<CrystalEntity, AccumEnergyComponent>) -> f64 {
+//Средняя по объему поликристалла запасенная энергия
+pub fn calc_mean_accum_energy(
+    est_map: &HashMap<CrystalEntity, AccumEnergyComponent>,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+) -> f64 {
     let mut value = 0.0;
-    for est_component in est_map.values() {
-        let est = est_component.get_value();
-        value += est;
+    let mut summ = 0.0;
+    for entity in sorted_entities(est_map) {
+        let est = est_map[entity].get_value();
+        let weight = gr_size_map.get(entity).expect("Ошибка поиска компонента gr_size").get_value().powi(3);
+        value += est * weight;
+        summ += weight;
     }
-    (1.0 / est_map.len() as f64) * value
+    if summ > 0.0 { value / summ } else { 0.0 }
 }
 
 pub fn get_subgrains(
     subgrains_component:&mut SubGrainsComponent,
     r0:f64,
     num:usize,
+    rng: &mut impl Rng,
 ){
     let mut values: Vec<f64> = Vec::new();
 
-    get_distr_rayleigh(&mut values, num, r0);
+    get_distr_rayleigh(&mut values, num, r0, rng);
 
     for value in values {
         subgrains_component.push_value(value);
@@ -77,12 +91,14 @@ pub fn initialize_subgrains(
     subgrains_map: &mut HashMap<CrystalEntity, SubGrainsComponent>,
     r0: f64,
     num: usize,
+    rng_map: &mut HashMap<CrystalEntity, RngComponent>,
 ) {
     // Вектор для хранения значений из файла
-    for subgrains_component in subgrains_map.values_mut() {
+    for (entity, subgrains_component) in subgrains_map.iter_mut() {
         let mut values: Vec<f64> = Vec::new();
-        
-        get_distr_rayleigh(&mut values, num, r0);
+        let rng_component = rng_map.get_mut(entity).expect("Ошибка поиска генератора случайных чисел");
+
+        get_distr_rayleigh(&mut values, num, r0, rng_component.get_rng());
 
         for value in values {
             subgrains_component.push_value(value);
@@ -90,13 +106,12 @@ pub fn initialize_subgrains(
     }
 }
 
-pub fn get_distr_rayleigh(distr: &mut Vec<f64>, num: usize, r0: f64) {
+pub fn get_distr_rayleigh(distr: &mut Vec<f64>, num: usize, r0: f64, rng: &mut impl Rng) {
     let mut ev_dist = Vec::new();
     let mut distr_den = Vec::new();
     let mut rando;
     let mut max: f64;
     let mut nnum = num;
-    let mut rng = rand::thread_rng();
     loop {
         nnum += num / 2;
         ev_dist.clear();
@@ -156,11 +171,85 @@ pub fn initialize_drive_force_recr(
     }
 }
 
+//Укрупнение субзерен внутри каждого зерна: dR/dt = M egb (1/R_cr - 1/R), R_cr = sum R^2 / sum R,
+//при котором суммарный объем субзерен зерна не меняется. Крупные субзерна растут за счет мелких;
+//субзерно, радиус которого стал бы меньше SUBGRAIN_REMOVED_R, исчезает
+pub fn calc_subgrain_coarsening(
+    subgrains_map: &mut HashMap<CrystalEntity, SubGrainsComponent>,
+    egb: f64,
+    m0: f64,
+    q: f64,
+    r: f64,
+    temp: f64,
+    dt: f64,
+) {
+    let mobility = m0 * (-q / (r * temp)).exp();
+    if mobility <= 0.0 {
+        return;
+    }
+    for subgrains_component in subgrains_map.values_mut() {
+        let (mut summ_r, mut summ_r2) = (0.0, 0.0);
+        for index in 0..subgrains_component.len() {
+            let subgrain_r = subgrains_component.get_value(index).unwrap();
+            if subgrain_r > SUBGRAIN_REMOVED_R {
+                summ_r += subgrain_r;
+                summ_r2 += subgrain_r * subgrain_r;
+            }
+        }
+        if summ_r <= 0.0 {
+            continue;
+        }
+        let r_cr = summ_r2 / summ_r;
+        for index in 0..subgrains_component.len() {
+            let subgrain_r = subgrains_component.get_value(index).unwrap();
+            if subgrain_r <= SUBGRAIN_REMOVED_R {
+                continue;
+            }
+            let value = subgrain_r + mobility * egb * (1.0 / r_cr - 1.0 / subgrain_r) * dt;
+            subgrains_component.set_value(index, value.max(SUBGRAIN_REMOVED_R));
+        }
+    }
+}
+
+//Объемная доля частиц второй фазы в момент time. При precipitation_time = 0 доля постоянна,
+//иначе частицы выделяются по закону JMAK: f = f0 + (f - f0)(1 - exp(-(t/tau)^n))
+pub fn calc_particle_fraction(
+    fraction: f64,
+    initial_fraction: f64,
+    precipitation_time: f64,
+    precipitation_n: f64,
+    time: f64,
+) -> f64 {
+    if precipitation_time <= 0.0 {
+        return fraction;
+    }
+    let precipitated = 1.0 - (-(time.max(0.0) / precipitation_time).powf(precipitation_n)).exp();
+    initial_fraction + (fraction - initial_fraction) * precipitated
+}
+
+//Давление закрепления границ частицами (Зинер): 3 f egb / 2 r
+pub fn calc_zener_pressure(fraction: f64, egb: f64, radius: f64) -> f64 {
+    1.5 * fraction * egb / radius
+}
+
+//Закрепление препятствует движению границы в любом направлении: движущая сила по модулю
+//уменьшается на давление Зинера, а при меньшей силе граница неподвижна
+pub fn apply_zener_pinning(value: f64, zener_pressure: f64) -> f64 {
+    if value > zener_pressure {
+        value - zener_pressure
+    } else if value < -zener_pressure {
+        value + zener_pressure
+    } else {
+        0.0
+    }
+}
+
 pub fn calc_drive_force_recr(
     df_recr_map: &mut HashMap<CrystalEntity, DriveForceRecrComponent>,
     subgrains_map: &HashMap<CrystalEntity, SubGrainsComponent>,
     est_poly_component: &AccumEnergyComponent,
     egb: f64,
+    zener_pressure: f64,
 ) {
     for (entity, df_recr_component) in df_recr_map.iter_mut() {
         if let Some(subgrains_component) = subgrains_map.get(entity) {
@@ -168,7 +257,7 @@ pub fn calc_drive_force_recr(
                 let subgrains_r = subgrains_component.get_value(index).unwrap();
                 let est_poly = est_poly_component.get_value();
                 let value = est_poly - 3.0 * egb / subgrains_r;                
-                df_recr_component.set_value(index, value);
+                df_recr_component.set_value(index, apply_zener_pinning(value, zener_pressure));
             }
         } else {
             panic!("Ошибка поиска компонента subgrains")
@@ -182,6 +271,7 @@ pub fn calc_drive_force_recr_cryst(
     gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
     est_poly_component: &AccumEnergyComponent,
     egb: f64,
+    zener_pressure: f64,
 ) {
     for (entity, df_recr_cryst_component) in df_recr_cryst_map.iter_mut() {
         if let Some(status_component) = status_map.get(entity){
@@ -190,7 +280,7 @@ pub fn calc_drive_force_recr_cryst(
                     let gr_size = gr_size_component.get_value();
                     let est_poly = est_poly_component.get_value();
                     let value = est_poly - 3.0 * egb / gr_size;
-                    df_recr_cryst_component.set_value(value);
+                    df_recr_cryst_component.set_value(apply_zener_pinning(value, zener_pressure));
                 } else {
                     panic!("Ошибка поиска компонента gr_size")
                 }
@@ -199,6 +289,55 @@ pub fn calc_drive_force_recr_cryst(
     }
 }
 
+//Движущая сила роста субзерен по собственной запасенной энергии зерна: зародыш свободен от
+//дислокаций, поэтому разность энергий зародыша и окружения равна запасенной энергии зерна
+pub fn calc_drive_force_recr_grain(
+    df_recr_map: &mut HashMap<CrystalEntity, DriveForceRecrComponent>,
+    subgrains_map: &HashMap<CrystalEntity, SubGrainsComponent>,
+    est_map: &HashMap<CrystalEntity, AccumEnergyComponent>,
+    egb: f64,
+    zener_pressure: f64,
+) {
+    for (entity, df_recr_component) in df_recr_map.iter_mut() {
+        if let (Some(subgrains_component), Some(est_component)) = (subgrains_map.get(entity), est_map.get(entity)) {
+            let est = est_component.get_value();
+            for index in 0..df_recr_component.len() {
+                let subgrains_r = subgrains_component.get_value(index).unwrap();
+                let value = est - 3.0 * egb / subgrains_r;
+                df_recr_component.set_value(index, apply_zener_pinning(value, zener_pressure));
+            }
+        } else {
+            panic!("Ошибка поиска компонента subgrains или est")
+        }
+    }
+}
+
+//Движущая сила миграции границ рекристаллизованного зерна: разность средней запасенной энергии
+//окружения и собственной энергии зерна, накопленной после зарождения
+pub fn calc_drive_force_recr_cryst_grain(
+    df_recr_cryst_map: &mut HashMap<CrystalEntity, DriveForceRecrCrystComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    est_map: &HashMap<CrystalEntity, AccumEnergyComponent>,
+    est_poly_component: &AccumEnergyComponent,
+    egb: f64,
+    zener_pressure: f64,
+) {
+    for (entity, df_recr_cryst_component) in df_recr_cryst_map.iter_mut() {
+        if let Some(status_component) = status_map.get(entity) {
+            if status_component.get_value() {
+                if let (Some(gr_size_component), Some(est_component)) = (gr_size_map.get(entity), est_map.get(entity)) {
+                    let gr_size = gr_size_component.get_value();
+                    let value = est_poly_component.get_value() - est_component.get_value() - 3.0 * egb / gr_size;
+                    df_recr_cryst_component.set_value(apply_zener_pinning(value, zener_pressure));
+                } else {
+                    panic!("Ошибка поиска компонента gr_size или est")
+                }
+            }
+        }
+    }
+}
+
 pub fn calc_facet_mobility(
     facet_mobility_map: &mut HashMap<CrystalEntity, FacetMobilityComponent>,
     m0: f64,
@@ -247,50 +386,262 @@ pub fn calc_grain_size(
     for (entity, gr_size_component) in gr_size_map.iter_mut() {
         if let Some(vel_facet_component) = vel_facet_map.get(entity) {
             if let Some(tau_c_component) = tau_c_map.get_mut(entity){
-                
                 let vel_facet = vel_facet_component.get_value();
                 let gr_size = gr_size_component.get_value();
                 let value = gr_size + vel_facet * dt;
-                gr_size_component.set_value(value);
-                for index in 0..24{
-                    let tau_c = tau_c_component.get_values(index).unwrap();
-                    let tau_c_witout_hp = tau_c - k_y*(b / gr_size).sqrt() / MEGA;
-                    let new_tau_c = tau_c_witout_hp + k_y*(b / value).sqrt() / MEGA;
-                    tau_c_component.set_values(index, new_tau_c);
-                }
+                set_grain_size_hp(gr_size_component, tau_c_component, value, b, k_y);
             }
         }
     }
 }
 
+//Новый размер зерна с пересчетом слагаемого Холла-Петча в критических напряжениях.
+//Для исчезнувшего зерна (нулевой размер) критические напряжения не меняются
+pub fn set_grain_size_hp(
+    gr_size_component: &mut GrainSizeComponent,
+    tau_c_component: &mut TauComponent,
+    value: f64,
+    b: f64,
+    k_y: f64,
+) {
+    let gr_size = gr_size_component.get_value();
+    gr_size_component.set_value(value);
+    if gr_size <= 0.0 || value <= 0.0 {
+        return;
+    }
+    for index in 0..24 {
+        let tau_c = tau_c_component.get_values(index).unwrap();
+        let tau_c_witout_hp = tau_c - k_y * (b / gr_size).sqrt();
+        tau_c_component.set_values(index, tau_c_witout_hp + k_y * (b / value).sqrt());
+    }
+}
+
+pub fn calc_grain_volume(gr_size: f64) -> f64 {
+    4.0 * std::f64::consts::PI * gr_size.powi(3) / 3.0
+}
+
+pub fn calc_grain_radius(volume: f64) -> f64 {
+    (volume.max(0.0) * 3.0 / 4.0 / std::f64::consts::PI).cbrt()
+}
+
+//Миграция границ с сохранением объема: рекристаллизованные зерна растут со скоростью границы,
+//деформированные зерна теряют тот же объем пропорционально площади своей поверхности.
+//Если деформированные зерна не могут отдать весь объем, рост всех рекристаллизованных зерен
+//уменьшается в одинаковое число раз. Деформированное зерно, радиус которого стал бы меньше min_gr_size,
+//отдает весь объем. Возвращает объем, перешедший к рекристаллизованным зернам
+#[allow(clippy::too_many_arguments)]
+pub fn calc_grain_growth(
+    gr_size_map: &mut HashMap<CrystalEntity, GrainSizeComponent>,
+    tau_c_map: &mut HashMap<CrystalEntity, TauComponent>,
+    vel_facet_map: &HashMap<CrystalEntity, VelocityFacetComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+    b: f64,
+    k_y: f64,
+    min_gr_size: f64,
+    dt: f64,
+) -> f64 {
+    let entities: Vec<CrystalEntity> = sorted_entities(gr_size_map).into_iter().cloned().collect();
+    let is_recrystallized = |entity: &CrystalEntity| status_map.get(entity).is_some_and(|status| status.get_value());
+
+    let mut gain = Vec::new();
+    let mut gain_total = 0.0;
+    let mut area_total = 0.0;
+    for entity in entities.iter() {
+        let gr_size = gr_size_map[entity].get_value();
+        if is_recrystallized(entity) {
+            let vel_facet = vel_facet_map.get(entity).expect("Ошибка поиска компонента vel_facet").get_value();
+            let value = calc_grain_volume(gr_size + vel_facet * dt) - calc_grain_volume(gr_size);
+            gain_total += value;
+            gain.push(value);
+        } else {
+            area_total += gr_size.powi(2);
+            gain.push(0.0);
+        }
+    }
+    if gain_total <= 0.0 || area_total <= 0.0 {
+        return 0.0;
+    }
+
+    //Объем, отданный деформированными зернами
+    let mut consumed = 0.0;
+    for entity in entities.iter().filter(|entity| !is_recrystallized(entity)) {
+        let gr_size = gr_size_map[entity].get_value();
+        let volume = calc_grain_volume(gr_size);
+        let mut loss = (gain_total * gr_size.powi(2) / area_total).min(volume);
+        if volume - loss < calc_grain_volume(min_gr_size) {
+            loss = volume;
+        }
+        consumed += loss;
+        let tau_c_component = tau_c_map.get_mut(entity).expect("Ошибка поиска компонента tau_c");
+        let gr_size_component = gr_size_map.get_mut(entity).unwrap();
+        set_grain_size_hp(gr_size_component, tau_c_component, calc_grain_radius(volume - loss), b, k_y);
+    }
+
+    let scale = consumed / gain_total;
+    for (entity, value) in entities.iter().zip(gain.iter()) {
+        if *value > 0.0 {
+            let gr_size = gr_size_map[entity].get_value();
+            let new_gr_size = calc_grain_radius(calc_grain_volume(gr_size) + scale * value);
+            let tau_c_component = tau_c_map.get_mut(entity).expect("Ошибка поиска компонента tau_c");
+            let gr_size_component = gr_size_map.get_mut(entity).unwrap();
+            set_grain_size_hp(gr_size_component, tau_c_component, new_gr_size, b, k_y);
+        }
+    }
+    consumed
+}
+
+//Поглощенные зерна (радиус меньше min_gr_size) публикуются в очередь событий для удаления.
+//Последнее зерно поликристалла не удаляется
+pub fn check_consumed_grains(
+    event_queue: &mut EventQueueComponent,
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    min_gr_size: f64,
+    time: f64,
+) {
+    let mut remaining = gr_size_map.len();
+    for entity in sorted_entities(gr_size_map) {
+        if remaining > 1 && gr_size_map[entity].get_value() < min_gr_size {
+            event_queue.push_event(GrainEvent::GrainConsumed { entity: entity.clone(), time });
+            remaining -= 1;
+        }
+    }
+}
+
+//Суммарный объем всех зерен и объем рекристаллизованных зерен
+pub fn calc_volumes(
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+) -> (f64, f64) {
+    let mut total_v = 0.0;
+    let mut recrystallized_v = 0.0;
+    for entity in sorted_entities(gr_size_map) {
+        let grain_v = calc_grain_volume(gr_size_map[entity].get_value());
+        total_v += grain_v;
+        if status_map.get(entity).is_some_and(|status| status.get_value()) {
+            recrystallized_v += grain_v;
+        }
+    }
+    (total_v, recrystallized_v)
+}
+
+//Баланс объема в volume.dat: время, полный объем, объем рекристаллизованных и деформированных зерен (м^3)
+//и относительное отклонение полного объема от начального
+pub fn write_volume_to_file(
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+    initial_volume: f64,
+    time: f64,
+    output_path: &Path,
+) {
+    let path = output_path.join("volume.dat");
+    let new_file = path.metadata().map_or(true, |metadata| metadata.len() == 0);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .expect("Ошибка открытия файла volume.dat");
+    let mut buf_writer = BufWriter::new(file);
+    if new_file {
+        writeln!(buf_writer, "# time\ttotal_volume\trecrystallized_volume\tdeformed_volume\tvolume_error")
+            .expect("Ошибка записи заголовка в volume.dat");
+    }
+    let (total_v, recrystallized_v) = calc_volumes(gr_size_map, status_map);
+    let error = if initial_volume > 0.0 { total_v / initial_volume - 1.0 } else { 0.0 };
+    writeln!(
+        buf_writer,
+        "{}\t{:.6e}\t{:.6e}\t{:.6e}\t{:.3e}",
+        time,
+        total_v,
+        recrystallized_v,
+        total_v - recrystallized_v,
+        error
+    )
+    .expect("Ошибка записи в volume.dat");
+    buf_writer.flush().expect("Ошибка завершения записи в volume.dat");
+}
+
+//Гистограмма радиусов существующих субзерен всех зерен: bins равных интервалов от 0 до r_edge.
+//r_edge равна r_max, а при r_max = 0 - наибольшему радиусу на шаге вывода, поэтому при укрупнении
+//субзерен интервалы растягиваются. Субзерна крупнее r_edge попадают в последний интервал.
+//Верхняя граница r_edge записывается в каждую строку: интервал n_i = [(i-1) r_edge/bins, i r_edge/bins)
+pub fn write_subgrain_histogram_to_file(
+    subgrains_map: &HashMap<CrystalEntity, SubGrainsComponent>,
+    r_max: f64,
+    bins: usize,
+    time: f64,
+    output_path: &Path,
+) {
+    let path = output_path.join("subgrains.dat");
+    let new_file = path.metadata().map_or(true, |metadata| metadata.len() == 0);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .expect("Ошибка открытия файла subgrains.dat");
+    let mut buf_writer = BufWriter::new(file);
+    if new_file {
+        let counts: Vec<String> = (1..=bins).map(|index| format!("n_{}", index)).collect();
+        writeln!(buf_writer, "# time\tcount\tr_mean\tr_max\tr_edge\t{}", counts.join("\t"))
+            .expect("Ошибка записи заголовка в subgrains.dat");
+    }
+    let radii: Vec<f64> = sorted_entities(subgrains_map)
+        .into_iter()
+        .flat_map(|entity| {
+            let subgrains_component = &subgrains_map[entity];
+            (0..subgrains_component.len()).map(|index| subgrains_component.get_value(index).unwrap())
+        })
+        .filter(|r| *r > SUBGRAIN_REMOVED_R)
+        .collect();
+    let max = radii.iter().cloned().fold(0.0, f64::max);
+    let r_edge = if r_max > 0.0 { r_max } else { max };
+    let histogram = calc_subgrain_histogram(&radii, r_edge, bins);
+    let mean = if radii.is_empty() { 0.0 } else { radii.iter().sum::<f64>() / radii.len() as f64 };
+    let counts: Vec<String> = histogram.iter().map(|value| value.to_string()).collect();
+    writeln!(buf_writer, "{}\t{}\t{:.6e}\t{:.6e}\t{:.6e}\t{}", time, radii.len(), mean, max, r_edge, counts.join("\t"))
+        .expect("Ошибка записи в subgrains.dat");
+    buf_writer.flush().expect("Ошибка завершения записи в subgrains.dat");
+}
 
+//Число радиусов в bins равных интервалах от 0 до r_edge, радиусы от r_edge и больше - в последнем
+pub fn calc_subgrain_histogram(radii: &[f64], r_edge: f64, bins: usize) -> Vec<usize> {
+    let mut histogram = vec![0usize; bins];
+    if r_edge <= 0.0 {
+        return histogram;
+    }
+    for r in radii.iter() {
+        histogram[((r / r_edge * bins as f64) as usize).min(bins - 1)] += 1;
+    }
+    histogram
+}
 
 pub fn init_grain_size(
     gr_size_map: &mut HashMap<CrystalEntity, GrainSizeComponent>,
     mean: f64,
     std_dev: f64,
+    rng_map: &mut HashMap<CrystalEntity, RngComponent>,
 ) {
-    for gr_size_component in gr_size_map.values_mut() {
-        let value = generate_lognormal_random_number(mean, std_dev);
+    for (entity, gr_size_component) in gr_size_map.iter_mut() {
+        let rng_component = rng_map.get_mut(entity).expect("Ошибка поиска генератора случайных чисел");
+        let value = generate_lognormal_random_number(mean, std_dev, rng_component.get_rng());
 
         gr_size_component.set_value(value-1.0);
     }
 }
 
-fn generate_lognormal_random_number(mean: f64, std_dev: f64) -> f64 {
-    let mut rng = rand::thread_rng();
+fn generate_lognormal_random_number(mean: f64, std_dev: f64, rng: &mut impl Rng) -> f64 {
     let lognormal = LogNormal::new(mean, std_dev).unwrap();
-    lognormal.sample(&mut rng)
+    lognormal.sample(rng)
 }
 
 pub fn check_new_grain(
-    new_grains: &mut NewGrainsComponent,
+    event_queue: &mut EventQueueComponent,
     df_recr_map: &HashMap<CrystalEntity, DriveForceRecrComponent>,
     gr_size_map: &mut HashMap<CrystalEntity, GrainSizeComponent>,
     subgrains_map: &mut HashMap<CrystalEntity, SubGrainsComponent>,
+    time: f64,
 ) {
-    let mut i:i64=0;
-    for (entity, df_recr_component) in df_recr_map.iter() {
+    for entity in sorted_entities(df_recr_map) {
+        let df_recr_component = &df_recr_map[entity];
         for index in 0..df_recr_component.len() {
             let value = df_recr_component.get_value(index).unwrap();
             if value>0.0 {
@@ -302,10 +653,13 @@ pub fn check_new_grain(
                         let subgrain_v = 4.0*std::f64::consts::PI*subgrain_r.powf(3.0)/3.0;
                         if grain_v-subgrain_v>0.0{
                             let new_gr_size = ((grain_v-subgrain_v)*3.0/4.0/std::f64::consts::PI).powf(1.0/3.0);
-                            i+=1;
-                            new_grains.push_value(subgrain_r);
+                            event_queue.push_event(GrainEvent::GrainNucleated {
+                                parent: entity.clone(),
+                                radius: subgrain_r,
+                                time,
+                            });
                             gr_size_component.set_value(new_gr_size);
-                            subgrains_component.set_value(index, 1.0e-17);
+                            subgrains_component.set_value(index, SUBGRAIN_REMOVED_R);
                         }
                     }
                 }
@@ -315,12 +669,216 @@ pub fn check_new_grain(
     
 }
 
+//Объемная доля рекристаллизованных зерен (объем зерна пропорционален кубу радиуса)
+pub fn calc_recrystallized_fraction(
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+)->f64{
+    let mut total_v = 0.0;
+    let mut recrystallized_v = 0.0;
+    for entity in sorted_entities(gr_size_map){
+        let grain_v = gr_size_map[entity].get_value().powi(3);
+        total_v += grain_v;
+        if status_map.get(entity).is_some_and(|status| status.get_value()) {
+            recrystallized_v += grain_v;
+        }
+    }
+    if total_v > 0.0 { recrystallized_v / total_v } else { 0.0 }
+}
+
+//Объемная доля рекристаллизованных зерен и их число в recryst.dat
+pub fn write_recrystallized_fraction_to_file(
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+    zener_pressure: f64,
+    time: f64,
+    output_path: &Path,
+) {
+    let path = output_path.join("recryst.dat");
+    let new_file = path.metadata().map_or(true, |metadata| metadata.len() == 0);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .expect("Ошибка открытия файла recryst.dat");
+    let mut buf_writer = BufWriter::new(file);
+    if new_file {
+        writeln!(buf_writer, "# time\trecrystallized_fraction\trecrystallized_grains\tzener_pressure")
+            .expect("Ошибка записи заголовка в recryst.dat");
+    }
+    let count = status_map.values().filter(|status| status.get_value()).count();
+    writeln!(
+        buf_writer,
+        "{}\t{:.6e}\t{}\t{:.6e}",
+        time,
+        calc_recrystallized_fraction(gr_size_map, status_map),
+        count,
+        zener_pressure
+    )
+    .expect("Ошибка записи в recryst.dat");
+    buf_writer.flush().expect("Ошибка завершения записи в recryst.dat");
+}
+
+pub fn fit_lognormal(values: &[f64]) -> Option<LognormalFit> {
+    if values.is_empty() || values.iter().any(|value| *value <= 0.0) {
+        return None;
+    }
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let std_dev = (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count).sqrt();
+    let mu = values.iter().map(|value| value.ln()).sum::<f64>() / count;
+    let sigma = (values.iter().map(|value| (value.ln() - mu).powi(2)).sum::<f64>() / count).sqrt();
+    Some(LognormalFit { count: values.len(), mean, std_dev, mu, sigma })
+}
+
+//Логарифмически равномерные границы интервалов от наименьшего до наибольшего радиуса.
+//Неположительные радиусы не учитываются; если положительных нет, все границы нулевые
+pub fn get_grain_size_edges(values: &[f64], bins: usize) -> Vec<f64> {
+    let positive = values.iter().cloned().filter(|value| *value > 0.0);
+    let min = positive.clone().fold(f64::INFINITY, f64::min);
+    let max = positive.fold(0.0, f64::max);
+    if max <= 0.0 {
+        return vec![0.0; bins + 1];
+    }
+    let (min, max) = if min < max { (min, max) } else { (0.99 * min, 1.01 * max) };
+    let ratio = (max / min).ln() / bins as f64;
+    (0..=bins).map(|index| if index == bins { max } else { min * (ratio * index as f64).exp() }).collect()
+}
+
+//Доли числа и объема зерен по интервалам edges. Зерна на правой границе попадают в последний интервал,
+//неположительные радиусы (поглощенные, но еще не удаленные зерна) не учитываются, как и в границах интервалов
+pub fn calc_grain_size_histogram(values: &[f64], edges: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let bins = edges.len() - 1;
+    let mut number = vec![0.0; bins];
+    let mut volume = vec![0.0; bins];
+    for value in values.iter().filter(|value| **value > 0.0) {
+        let index = edges[1..].iter().position(|edge| value < edge).unwrap_or(bins - 1);
+        number[index] += 1.0;
+        volume[index] += value.powi(3);
+    }
+    for histogram in [&mut number, &mut volume] {
+        let summ: f64 = histogram.iter().sum();
+        if summ > 0.0 {
+            histogram.iter_mut().for_each(|value| *value /= summ);
+        }
+    }
+    (number, volume)
+}
+
+fn cumulative(values: &[f64]) -> Vec<f64> {
+    values
+        .iter()
+        .scan(0.0, |summ, value| {
+            *summ += value;
+            Some(*summ)
+        })
+        .collect()
+}
+
+//Распределение радиусов зерен на шаге step: для всех, деформированных и рекристаллизованных зерен
+//доли по числу и по объему (r^3) с накопленными значениями, в заголовке - параметры логнормального
+//распределения каждой группы
+pub fn write_grain_size_distribution(
+    gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>,
+    status_map: &HashMap<CrystalEntity, StatusRecrystComponent>,
+    bins: usize,
+    step: i64,
+    time: f64,
+    path: &Path,
+) -> io::Result<()> {
+    let mut all = Vec::new();
+    let mut deformed = Vec::new();
+    let mut recrystallized = Vec::new();
+    for entity in sorted_entities(gr_size_map) {
+        let gr_size = gr_size_map[entity].get_value();
+        //Поглощенные, но еще не удаленные зерна в распределение не входят
+        if gr_size <= 0.0 {
+            continue;
+        }
+        all.push(gr_size);
+        match status_map.get(entity).map(|status| status.get_value()) {
+            Some(true) => recrystallized.push(gr_size),
+            _ => deformed.push(gr_size),
+        }
+    }
+    let groups = [("all", &all), ("deformed", &deformed), ("recrystallized", &recrystallized)];
+    let edges = get_grain_size_edges(&all, bins);
+
+    let mut buf_writer = BufWriter::new(File::create(path)?);
+    writeln!(buf_writer, "# step {} time {}", step, time)?;
+    writeln!(buf_writer, "# group\tcount\tmean\tstd_dev\tmu\tsigma")?;
+    for (name, values) in groups.iter() {
+        match fit_lognormal(values) {
+            Some(fit) => writeln!(
+                buf_writer,
+                "# {}\t{}\t{:.6e}\t{:.6e}\t{:.6}\t{:.6}",
+                name, fit.count, fit.mean, fit.std_dev, fit.mu, fit.sigma
+            )?,
+            None => writeln!(buf_writer, "# {}\t0\t-\t-\t-\t-", name)?,
+        }
+    }
+    let mut header = vec!["r_min".to_string(), "r_max".to_string()];
+    for (name, _) in groups.iter() {
+        for column in ["number", "number_cdf", "volume", "volume_cdf"] {
+            header.push(format!("{}_{}", name, column));
+        }
+    }
+    writeln!(buf_writer, "# {}", header.join("\t"))?;
+
+    let columns: Vec<[Vec<f64>; 4]> = groups
+        .iter()
+        .map(|(_, values)| {
+            let (number, volume) = calc_grain_size_histogram(values, &edges);
+            let (number_cdf, volume_cdf) = (cumulative(&number), cumulative(&volume));
+            [number, number_cdf, volume, volume_cdf]
+        })
+        .collect();
+    for index in 0..bins {
+        write!(buf_writer, "{:.6e}\t{:.6e}", edges[index], edges[index + 1])?;
+        for group in columns.iter() {
+            for column in group.iter() {
+                write!(buf_writer, "\t{:.6e}", column[index])?;
+            }
+        }
+        writeln!(buf_writer)?;
+    }
+    buf_writer.flush()
+}
+
+//Параметры JMAK по истории доли рекристаллизованных зерен: линейная регрессия
+//ln(-ln(1-X)) = ln k + n ln t по точкам с 0 < X < 1 и t > 0.
+//Если таких точек меньше двух или все они на одном времени, возвращается None
+pub fn fit_jmak(history: &RecrystHistoryComponent) -> Option<JmakFit> {
+    let points: Vec<(f64, f64)> = history
+        .get_values()
+        .iter()
+        .filter(|(time, fraction)| *time > 0.0 && *fraction > 0.0 && *fraction < 1.0)
+        .map(|(time, fraction)| (time.ln(), (-(1.0 - fraction).ln()).ln()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let num = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / num;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / num;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let syy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if sxx <= 0.0 {
+        return None;
+    }
+    let n = sxy / sxx;
+    let ln_k = mean_y - n * mean_x;
+    let r2 = if syy > 0.0 { sxy * sxy / (sxx * syy) } else { 1.0 };
+    Some(JmakFit { n, k: ln_k.exp(), r2, points: points.len() })
+}
+
 pub fn calc_mean_grain_size(
     gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>
 )->f64{
     let mut mean_gr_size = 0.0;
-    for gr_size_component in gr_size_map.values(){
-        let value=gr_size_component.get_value();
+    for entity in sorted_entities(gr_size_map){
+        let value=gr_size_map[entity].get_value();
         
         mean_gr_size+=value;
     }
@@ -329,23 +887,145 @@ pub fn calc_mean_grain_size(
 
 pub fn print_mean_grainsize_to_file(
     gr_size_map: & HashMap<CrystalEntity, GrainSizeComponent>,
-    dt:f64,
-    step:i64,
+    time: f64,
+    output_path: &Path,
 ){
     let file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(PathBuf::from(FILE_OUTPUT_PATH).join("grsize.dat"))
+        .open(output_path.join("grsize.dat"))
         .expect("Ошибка открытия файла grsize.dat");
-    //let file = File::create(FILE_OUTPUT_PATH.to_string() + "din.dat")?;
     let mut buf_writer = BufWriter::with_capacity(4 * (10 + 1 + 10 + 1) * 3, file);
     write!(buf_writer, "{:.4e}\t", calc_mean_grain_size(gr_size_map))
         .expect("Ошибка записи интенсивности деформации в grsize.dat");
     write!(buf_writer, "{:.4e}\t", gr_size_map.len())
         .expect("Ошибка записи интенсивности деформации в grsize.dat");
-    write!(buf_writer, "{}\t", dt * step as f64).expect("Ошибка записи времени в grsize.dat");
+    write!(buf_writer, "{}\t", time).expect("Ошибка записи времени в grsize.dat");
     writeln!(buf_writer).expect("Ошибка записи разделителя в rvout.dat");
     buf_writer
     .flush()
     .expect("Ошибка завершения записи в rvout.dat");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grain_size_edges_ignore_non_positive_radii() {
+        let edges = get_grain_size_edges(&[0.0, 1.0e-6, 4.0e-6], 2);
+        assert_eq!(edges.len(), 3);
+        assert!(edges.iter().all(|edge| edge.is_finite()));
+        assert_eq!(edges[0], 1.0e-6);
+        assert!((edges[1] - 2.0e-6).abs() < 1.0e-18);
+        assert_eq!(edges[2], 4.0e-6);
+
+        assert_eq!(get_grain_size_edges(&[0.0, -1.0], 3), vec![0.0; 4]);
+    }
+
+    #[test]
+    fn grain_size_histogram_ignores_non_positive_radii() {
+        let values = [0.0, 1.0e-6, 3.0e-6, 4.0e-6];
+        let edges = get_grain_size_edges(&values, 2);
+        let (number, volume) = calc_grain_size_histogram(&values, &edges);
+        assert_eq!(number, vec![1.0 / 3.0, 2.0 / 3.0]);
+        let total = 1.0 + 27.0 + 64.0;
+        assert!((volume[0] - 1.0 / total).abs() < 1.0e-12);
+        assert!((volume[1] - 91.0 / total).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn jmak_fit_recovers_exponent_and_rate() {
+        //X = 1 - exp(-0.02 t^2.5); точки X = 0 и X = 1 в регрессию не входят
+        let (n, k) = (2.5, 0.02);
+        let mut history = RecrystHistoryComponent::new();
+        history.push_value(0.0, 0.0);
+        for time in [1.0, 2.0, 3.0, 4.0, 5.0] {
+            history.push_value(time, 1.0 - (-k * f64::powf(time, n)).exp());
+        }
+        history.push_value(50.0, 1.0);
+
+        let fit = fit_jmak(&history).unwrap();
+        assert_eq!(fit.points, 5);
+        assert!((fit.n - n).abs() < 1.0e-9);
+        assert!((fit.k - k).abs() < 1.0e-9);
+        assert!((fit.r2 - 1.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn jmak_fit_needs_two_distinct_times() {
+        let mut history = RecrystHistoryComponent::new();
+        history.push_value(1.0, 0.5);
+        assert!(fit_jmak(&history).is_none());
+        history.push_value(1.0, 0.6);
+        assert!(fit_jmak(&history).is_none());
+    }
+
+    type Grains = (
+        HashMap<CrystalEntity, GrainSizeComponent>,
+        HashMap<CrystalEntity, TauComponent>,
+        HashMap<CrystalEntity, VelocityFacetComponent>,
+        HashMap<CrystalEntity, StatusRecrystComponent>,
+    );
+
+    //Зерна (радиус, рекристаллизовано, скорость границы) для проверки миграции границ
+    fn grains(values: &[(f64, bool, f64)]) -> Grains {
+        let (mut gr_size_map, mut tau_c_map, mut vel_facet_map, mut status_map) =
+            (HashMap::new(), HashMap::new(), HashMap::new(), HashMap::new());
+        for (id, &(gr_size, recrystallized, vel_facet)) in values.iter().enumerate() {
+            let entity = CrystalEntity::new(id as u32);
+            let mut gr_size_component = GrainSizeComponent::new();
+            gr_size_component.set_value(gr_size);
+            gr_size_map.insert(entity.clone(), gr_size_component);
+            tau_c_map.insert(entity.clone(), TauComponent::new());
+            let mut vel_facet_component = VelocityFacetComponent::new();
+            vel_facet_component.set_value(vel_facet);
+            vel_facet_map.insert(entity.clone(), vel_facet_component);
+            let mut status_component = StatusRecrystComponent::new();
+            status_component.set_value(recrystallized);
+            status_map.insert(entity, status_component);
+        }
+        (gr_size_map, tau_c_map, vel_facet_map, status_map)
+    }
+
+    fn total_volume(gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>) -> f64 {
+        gr_size_map.values().map(|component| calc_grain_volume(component.get_value())).sum()
+    }
+
+    #[test]
+    fn grain_growth_conserves_total_volume() {
+        let cases: [&[(f64, bool, f64)]; 2] = [
+            //Деформированные зерна отдают объем пропорционально площади поверхности
+            &[(2.0e-6, true, 1.0e-6), (3.0e-6, true, 5.0e-7), (2.0e-5, false, 0.0), (4.0e-5, false, 0.0)],
+            //Деформированное зерно мало и поглощается целиком, рост зародышей ограничен его объемом
+            &[(2.0e-5, true, 1.0e-3), (1.0e-6, false, 0.0)],
+        ];
+        for values in cases {
+            let (mut gr_size_map, mut tau_c_map, vel_facet_map, status_map) = grains(values);
+            let recrystallized_volume = |gr_size_map: &HashMap<CrystalEntity, GrainSizeComponent>| -> f64 {
+                gr_size_map
+                    .iter()
+                    .filter(|(entity, _)| status_map[entity].get_value())
+                    .map(|(_, component)| calc_grain_volume(component.get_value()))
+                    .sum()
+            };
+            let volume_before = total_volume(&gr_size_map);
+            let recrystallized_before = recrystallized_volume(&gr_size_map);
+
+            let moved = calc_grain_growth(&mut gr_size_map, &mut tau_c_map, &vel_facet_map, &status_map, 2.5e-10, 0.0, 1.0e-7, 1.0e-3);
+            assert!(moved > 0.0);
+            assert!((total_volume(&gr_size_map) - volume_before).abs() < 1.0e-12 * volume_before);
+            assert!((recrystallized_volume(&gr_size_map) - recrystallized_before - moved).abs() < 1.0e-12 * volume_before);
+        }
+    }
+
+    #[test]
+    fn subgrain_histogram_spans_up_to_the_edge() {
+        let radii = [0.5e-6, 1.5e-6, 2.5e-6, 4.0e-6];
+        assert_eq!(calc_subgrain_histogram(&radii, 4.0e-6, 4), vec![1, 1, 1, 1]);
+        //Субзерна крупнее заданной границы попадают в последний интервал
+        assert_eq!(calc_subgrain_histogram(&radii, 2.0e-6, 2), vec![1, 3]);
+        assert_eq!(calc_subgrain_histogram(&[], 0.0, 3), vec![0, 0, 0]);
+    }
+}
+
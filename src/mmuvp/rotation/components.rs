@@ -1,6 +1,8 @@
 #![allow(dead_code)]
+use serde::{Deserialize, Serialize};
 use nalgebra::Matrix3;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RotationComponent{
     tensor_form: Matrix3<f64>,
 }
@@ -34,6 +36,7 @@ impl RotationComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RotationRateComponent{
     tensor_form: Matrix3<f64>,
 }
@@ -67,6 +70,7 @@ impl RotationRateComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SpinComponent{
     tensor_form: Matrix3<f64>,
 }
@@ -1,29 +1,37 @@
 #![allow(dead_code)]
-use nalgebra::{Matrix3, Vector3};
-use rand::Rng;
+use nalgebra::{Matrix3, Rotation3, Vector3};
+use rand::{distributions::Distribution, Rng};
+use statrs::distribution::Normal;
 use std::{
     collections::HashMap,
     f64::consts::PI,
     fs::OpenOptions,
     io::{BufWriter, Write},
-    path::PathBuf,
+    path::Path,
 };
 
-use crate::{consts::FILE_OUTPUT_PATH, mmuvp::entity::CrystalEntity};
+use crate::mmuvp::{
+    entity::{sorted_entities, CrystalEntity},
+    params::components::NucleusOrientationModel,
+    random::components::RngComponent,
+};
 
 use super::components::RotationComponent;
 
-pub fn gen_uniform_distribution(rotation_map: &mut HashMap<CrystalEntity, RotationComponent>) {
+pub fn gen_uniform_distribution(
+    rotation_map: &mut HashMap<CrystalEntity, RotationComponent>,
+    rng_map: &mut HashMap<CrystalEntity, RngComponent>,
+) {
     if rotation_map.len() > 1 {
-        for rotation in rotation_map.values_mut() {
-            let matrix = get_uniform_distribution();
+        for (entity, rotation) in rotation_map.iter_mut() {
+            let rng_component = rng_map.get_mut(entity).expect("Ошибка поиска генератора случайных чисел");
+            let matrix = get_uniform_distribution(rng_component.get_rng());
             rotation.set_matrix(matrix).unwrap();
         }
     }
 }
 
-pub fn get_uniform_distribution()->Matrix3<f64>{
-    let mut rng = rand::thread_rng();
+pub fn get_uniform_distribution(rng: &mut impl Rng)->Matrix3<f64>{
     let a = rng.gen_range(0.0..2.0 * PI);
     let b: f64 = rng.gen_range(-1.0..1.0);
     let b = b.acos();
@@ -48,32 +56,92 @@ pub fn get_uniform_distribution()->Matrix3<f64>{
     )
 }
 
-pub fn write_pole_figure(rotation_map: &HashMap<CrystalEntity, RotationComponent>) {
+//Случайный поворот на малый угол: компоненты вектора поворота распределены нормально
+//со стандартным отклонением scatter (рад)
+pub fn get_misorientation(scatter: f64, rng: &mut impl Rng) -> Matrix3<f64> {
+    if scatter <= 0.0 {
+        return Matrix3::identity();
+    }
+    let normal = Normal::new(0.0, scatter).expect("Ошибка параметров нормального распределения");
+    let axis_angle = Vector3::new(normal.sample(rng), normal.sample(rng), normal.sample(rng));
+    Rotation3::new(axis_angle).into_inner()
+}
+
+//Поворот двойникования Σ3 в системе координат кристалла: 60° вокруг случайно выбранной оси <111>
+pub fn get_twin_rotation(rng: &mut impl Rng) -> Matrix3<f64> {
+    let axes = [
+        Vector3::new(1.0, 1.0, 1.0),
+        Vector3::new(-1.0, 1.0, 1.0),
+        Vector3::new(1.0, -1.0, 1.0),
+        Vector3::new(1.0, 1.0, -1.0),
+    ];
+    let axis = axes[rng.gen_range(0..axes.len())].normalize();
+    Rotation3::new(axis * PI / 3.0).into_inner()
+}
+
+//Ориентация зародыша по модели model с рассеянием scatter. Доля fraction зародышей получает
+//ориентацию по модели, остальные (и зародыши без исходного зерна) - случайную
+pub fn get_nucleus_orientation(
+    parent: Option<&Matrix3<f64>>,
+    model: NucleusOrientationModel,
+    scatter: f64,
+    fraction: f64,
+    rng: &mut impl Rng,
+) -> Matrix3<f64> {
+    if model == NucleusOrientationModel::Random || rng.gen_range(0.0..1.0) >= fraction {
+        return get_uniform_distribution(rng);
+    }
+    let base = match (model, parent) {
+        (NucleusOrientationModel::Cube, _) => Matrix3::identity(),
+        (NucleusOrientationModel::Inherit, Some(parent)) => *parent,
+        (NucleusOrientationModel::Twin, Some(parent)) => parent * get_twin_rotation(rng),
+        _ => return get_uniform_distribution(rng),
+    };
+    base * get_misorientation(scatter, rng)
+}
+
+//Углы Эйлера в конвенции Бунге (phi1, Phi, phi2), градусы. Тензор ориентации o переводит
+//кристаллографическую систему координат в лабораторную, матрица Бунге g = o^T
+pub fn get_euler_angles_bunge(o: &Matrix3<f64>) -> (f64, f64, f64) {
+    let g = o.transpose();
+    let big_phi = g[(2, 2)].clamp(-1.0, 1.0).acos();
+    let (phi1, phi2) = if big_phi.sin().abs() > 1.0e-9 {
+        (g[(2, 0)].atan2(-g[(2, 1)]), g[(0, 2)].atan2(g[(1, 2)]))
+    } else {
+        //Вырожденный случай Phi = 0 или 180: поворот задается только суммой phi1 + phi2
+        (g[(0, 1)].atan2(g[(0, 0)]), 0.0)
+    };
+    (
+        phi1.rem_euclid(2.0 * PI).to_degrees(),
+        big_phi.to_degrees(),
+        phi2.rem_euclid(2.0 * PI).to_degrees(),
+    )
+}
+
+pub fn write_pole_figure(rotation_map: &HashMap<CrystalEntity, RotationComponent>, output_path: &Path) {
     let file100 = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(PathBuf::from(FILE_OUTPUT_PATH).join("pole_fig100.dat"))
+        .open(output_path.join("pole_fig100.dat"))
         .expect("Ошибка открытия файла для записи poly_fig100.dat");
-    //let file100 = File::create(FILE_OUTPUT_PATH.to_string() + "pole_fig100.dat")?;
     let mut buf_writer100 = BufWriter::with_capacity(4 * 25 * 3 * rotation_map.len() + 4, file100);
 
     let file110 = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(PathBuf::from(FILE_OUTPUT_PATH).join("pole_fig110.dat"))
+        .open(output_path.join("pole_fig110.dat"))
         .expect("Ошибка открытия файла для записи pole_fig110.dat");
-    //let file110 = File::create(FILE_OUTPUT_PATH.to_string() + "pole_fig110.dat")?;
     let mut buf_writer110 = BufWriter::with_capacity(4 * 25 * 3 * rotation_map.len() + 4, file110);
 
     let file111 = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(PathBuf::from(FILE_OUTPUT_PATH).join("pole_fig111.dat"))
+        .open(output_path.join("pole_fig111.dat"))
         .expect("Ошибка открытия файла для записи pole_fig111.dat");
-    //let file111 = File::create(FILE_OUTPUT_PATH.to_string() + "pole_fig111.dat")?;
     let mut buf_writer111 = BufWriter::with_capacity(4 * 25 * 3 * rotation_map.len() + 4, file111);
 
-    for rotation in rotation_map.values() {
+    for entity in sorted_entities(rotation_map) {
+        let rotation = &rotation_map[entity];
         let test_vector100 = Vector3::new(1.0, 0.0, 0.0);
         let rotation_vector100 =
             (test_vector100.normalize().transpose() * rotation.get_tensor()).transpose();
@@ -119,17 +187,18 @@ pub fn write_pole_figure(rotation_map: &HashMap<CrystalEntity, RotationComponent
         .expect("Ошибка завершения записи полюсных фигур 111");
 }
 
-pub fn write_rotation_to_file(rotation_map: &HashMap<CrystalEntity, RotationComponent>) {
+pub fn write_rotation_to_file(rotation_map: &HashMap<CrystalEntity, RotationComponent>, output_path: &Path) {
     let file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(PathBuf::from(FILE_OUTPUT_PATH).join("orient.dat"))
+        .open(output_path.join("orient.dat"))
         .expect("Ошибка открытия файла orient.dat");
 
     let mut buf_writer = BufWriter::with_capacity(4 * 25 * 9 * rotation_map.len() + 4, file);
 
     // Записываем данные в файл
-    for rotation in rotation_map.values() {
+    for entity in sorted_entities(rotation_map) {
+        let rotation = &rotation_map[entity];
         let tensor_orient = rotation.get_tensor();
         for o_i in tensor_orient.iter() {
             write!(buf_writer, "{}\t", *o_i).expect("Ошибка записи тензора ориентации");
@@ -241,4 +310,34 @@ pub fn levi_civita(i:usize, j:usize, k:usize) -> f64 {
         0.0
     }
 }
- */
\ No newline at end of file
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn twin_rotation_is_60_degrees_about_111() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for _ in 0..20 {
+            let rotation = get_twin_rotation(&mut rng);
+            assert!((rotation * rotation.transpose() - Matrix3::identity()).norm() < 1.0e-12);
+            assert!((rotation.determinant() - 1.0).abs() < 1.0e-12);
+            //tr R = 1 + 2 cos 60° = 2
+            assert!((rotation.trace() - 2.0).abs() < 1.0e-12);
+
+            //Ось поворота R - R^T = 2 sin(60°) [w]x: все компоненты оси по модулю равны 1/sqrt(3)
+            let skew = rotation - rotation.transpose();
+            let axis = Vector3::new(skew[(2, 1)], skew[(0, 2)], skew[(1, 0)]) / (2.0 * (PI / 3.0).sin());
+            for component in axis.iter() {
+                assert!((component.abs() - 1.0 / 3.0_f64.sqrt()).abs() < 1.0e-12);
+            }
+
+            //Двойное двойникование - поворот на 120°, а не тождественное преобразование: tr R^2 = 1 + 2 cos 120° = 0
+            let twice = rotation * rotation;
+            assert!((twice - Matrix3::identity()).norm() > 1.0);
+            assert!(twice.trace().abs() < 1.0e-12);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
+use serde::{Deserialize, Serialize};
 
 use nalgebra::{Vector3, Matrix3};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BurgersVectorComponent {
     vectors: Vec<Vector3<f64>>,
 }
@@ -30,6 +32,7 @@ impl BurgersVectorComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NormalVectorComponent {
     vectors: Vec<Vector3<f64>>,
 }
@@ -58,6 +61,7 @@ impl NormalVectorComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BNComponent {
     matrixs: Vec<Matrix3<f64>>,
 }
@@ -85,6 +89,7 @@ impl BNComponent {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TauComponent {
     values: Vec<f64>,
 }
@@ -110,6 +115,7 @@ impl TauComponent {
     }   
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TauRateComponent {
     values: Vec<f64>,
 }
@@ -136,6 +142,7 @@ impl TauRateComponent {
 }
 
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GammaComponent {
     values: Vec<f64>,
 }
@@ -161,6 +168,7 @@ impl GammaComponent {
     }   
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GammaRateComponent {
     values: Vec<f64>,
 }
@@ -186,6 +194,7 @@ impl GammaRateComponent {
     }   
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HVectorComponent{
     vector:Vec<f64>,
 }
@@ -212,6 +221,7 @@ impl HVectorComponent{
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HMatrixComponent{
     matrix:Vec<Vec<f64>>,
 }
@@ -243,3 +253,29 @@ impl HMatrixComponent{
         }
     }
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SlipStatusComponent {
+    values: Vec<bool>,
+}
+
+impl SlipStatusComponent {
+    pub fn new() -> Self {
+        let values = vec![false; 24];
+        SlipStatusComponent { values }
+    }
+
+    pub fn set_values(&mut self, index: usize, value: bool) {
+        if index < self.values.len() {
+            self.values[index] = value;
+        }
+    }
+
+    pub fn get_values(&self, index: usize) -> Option<bool> {
+        if index < self.values.len() {
+            Some(self.values[index])
+        } else {
+            None
+        }
+    }
+}
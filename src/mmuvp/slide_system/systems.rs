@@ -1,34 +1,55 @@
 #![allow(dead_code)]
 
-use std::{
-    collections::HashMap, 
-    fs::File, path::PathBuf, 
-    io::{BufReader, BufRead}
-};
+use std::collections::HashMap;
 
 use nalgebra::{Vector3, Matrix3};
 
 use crate::{
-    mmuvp::{entity::CrystalEntity, elasticity::components::SigmaComponent}, 
-    consts::{FILE_INPUT_PATH, MEGA}, GrainSizeComponent};
+    mmuvp::{
+        entity::{sorted_entities, CrystalEntity},
+        elasticity::components::SigmaComponent,
+        events::components::{EventQueueComponent, GrainEvent},
+        recrystallization::components::GrainSizeComponent,
+    },
+};
 
 use super::components::*;
 
+//Системы скольжения ГЦК кристалла {111}<110>: направления Бюргерса и нормали плоскостей
+//(не нормированные). Каждая система дает две: с векторами b и -b
+pub const FCC_BURGERS: [[f64; 3]; 12] = [
+    [-1.0, 1.0, 0.0],
+    [1.0, 0.0, -1.0],
+    [0.0, -1.0, 1.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, -1.0, 0.0],
+    [0.0, 1.0, -1.0],
+    [-1.0, 0.0, 1.0],
+    [0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+];
+pub const FCC_NORMALS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 1.0],
+    [1.0, 1.0, 1.0],
+    [1.0, 1.0, 1.0],
+    [-1.0, 1.0, 1.0],
+    [-1.0, 1.0, 1.0],
+    [-1.0, 1.0, 1.0],
+    [1.0, -1.0, 1.0],
+    [1.0, -1.0, 1.0],
+    [1.0, -1.0, 1.0],
+    [-1.0, -1.0, 1.0],
+    [-1.0, -1.0, 1.0],
+    [-1.0, -1.0, 1.0],
+];
+
 pub fn get_burgers_vectors(
     burgers_component: &mut BurgersVectorComponent,
 ){
-    let file = File::open(PathBuf::from(FILE_INPUT_PATH).join("b.input")).expect("Ошибка открытия файла b.input");
-    let reader = BufReader::new(file);
-
-    for (index, line) in reader.lines().enumerate() {
-        let line = line.expect("Ошибка. Файл b.input заполнен неверно");
-        let values: Vec<f64> = line
-            .split_whitespace()
-            .map(|s| s.parse::<f64>().expect("Ошибка перевода строки b.input в числа"))
-            .collect();
-        if values.len()!=3 {
-            panic!("Ошибка. Количество элементов в b.input равно {}",values.len());
-        }
+    for (index, values) in FCC_BURGERS.iter().enumerate() {
         let vector = Vector3::new(values[0], values[1], values[2]).normalize(); 
         burgers_component.set_vector(index*2, vector);
         burgers_component.set_vector(index*2+1, -vector);
@@ -39,42 +60,13 @@ pub fn get_burgers_vectors(
 pub fn initialize_burgers_vectors(
     burgers_map: &mut HashMap<CrystalEntity, BurgersVectorComponent>,
 ) {
-    let file = File::open(PathBuf::from(FILE_INPUT_PATH).join("b.input")).expect("Ошибка открытия файла b.input");
-    let reader = BufReader::new(file);
-
-    for (index, line) in reader.lines().enumerate() {
-        let line = line.expect("Ошибка. Файл b.input заполнен неверно");
-        let values: Vec<f64> = line
-            .split_whitespace()
-            .map(|s| s.parse::<f64>().expect("Ошибка перевода строки b.input в числа"))
-            .collect();
-        if values.len()!=3 {
-            panic!("Ошибка. Количество элементов в b.input равно {}",values.len());
-        }
-        let vector = Vector3::new(values[0], values[1], values[2]).normalize();    
-        burgers_map.values_mut().for_each(|burgers_vector| {
-            if index < 12 {
-                burgers_vector.set_vector(index * 2, vector);
-                burgers_vector.set_vector(index * 2 + 1, -vector);
-            }
-        });         
-    }
+    burgers_map.values_mut().for_each(get_burgers_vectors);
 }
 
 pub fn get_normals_vector(
-    normal_vector_component:&mut NormalVectorComponent
+    normal_vector_component:&mut NormalVectorComponent,
 ){
-    let file = File::open(PathBuf::from(FILE_INPUT_PATH).join("n.input")).expect("Ошибка открытия файла n.input");
-    let reader = BufReader::new(file);
-    for (index, line) in reader.lines().enumerate() {
-        let line = line.expect("Ошибка. Файл n.input неверно заполнен");
-        let values: Vec<f64> = line
-            .split_whitespace()
-            .map(|s| s.parse::<f64>().expect("Ошибка перевода строки n.input в числа"))
-            .collect();
-        if values.len()!=3 {
-            panic!("Ошибка. Количество элементов в n.input равно {}",values.len());
-        }
+    for (index, values) in FCC_NORMALS.iter().enumerate() {
         let vector = Vector3::new(values[0], values[1], values[2]).normalize();
         normal_vector_component.set_vector(index*2, vector);
         normal_vector_component.set_vector(index*2+1, vector);
@@ -84,26 +76,7 @@ pub fn get_normals_vector(
 pub fn initialize_normal_vectors(
     normals_map: &mut HashMap<CrystalEntity, NormalVectorComponent>,
 ) {
-    let file = File::open(PathBuf::from(FILE_INPUT_PATH).join("n.input")).expect("Ошибка открытия файла n.input");
-    let reader = BufReader::new(file);
-
-    for (index, line) in reader.lines().enumerate() {
-        let line = line.expect("Ошибка. Файл n.input неверно заполнен");
-        let values: Vec<f64> = line
-            .split_whitespace()
-            .map(|s| s.parse::<f64>().expect("Ошибка перевода строки n.input в числа"))
-            .collect();
-        if values.len()!=3 {
-            panic!("Ошибка. Количество элементов в n.input равно {}",values.len());
-        }
-        let vector = Vector3::new(values[0], values[1], values[2]).normalize();
-        normals_map.values_mut().for_each(|normal_vector| {
-            if index < 12 {
-                normal_vector.set_vector(index * 2, vector);
-                normal_vector.set_vector(index * 2 + 1, vector);
-            }
-        });
-    }
+    normals_map.values_mut().for_each(get_normals_vector);
 }
 
 pub fn get_new_bn(
@@ -201,10 +174,9 @@ pub fn initialize_tau_c(
     tau_c_map: &mut HashMap<CrystalEntity, TauComponent>,
     tauc: f64,
 ) {
-    let value= tauc/MEGA;
     tau_c_map.values_mut().for_each(|tau_c| {
         for index in 0..24 {
-            tau_c.set_values(index, value);
+            tau_c.set_values(index, tauc);
         }
     });
 }
@@ -215,10 +187,9 @@ pub fn get_tauc(
     k_y: f64,
     d_g: f64,
 ){
-    let value= tauc/MEGA;
-    let addition_hp = k_y*(b / d_g).sqrt() / MEGA;
+    let addition_hp = k_y*(b / d_g).sqrt();
     for index in 0..24 {
-        tau_c_component.set_values(index, value + addition_hp);
+        tau_c_component.set_values(index, tauc + addition_hp);
     }
 }
 
@@ -229,14 +200,12 @@ pub fn initialize_tau_c_hp(
     b:f64,
     k_y: f64,
 ){
-    let value= tauc/MEGA;
-    
     for (entity, tau_c) in tau_c_map.iter_mut(){
         if let Some(gr_size_component) = gr_size_map.get(entity){
             let gr_size = gr_size_component.get_value();
-            let addition_hp = k_y*(b / gr_size).sqrt() / MEGA;
+            let addition_hp = k_y*(b / gr_size).sqrt();
             for index in 0..24 {
-                tau_c.set_values(index, value + addition_hp);
+                tau_c.set_values(index, tauc + addition_hp);
             }
         }
     }
@@ -354,8 +323,7 @@ pub fn calc_h_vector(
                 let tauc = tauc_component
                     .get_values(index)
                     .expect("Ошибка извлечения tau_c");
-                let tau_sat_mpa=tau_sat*1e-6;
-                let ratio = tauc/tau_sat_mpa;
+                let ratio = tauc/tau_sat;
                 let absol = (1.0-ratio).abs();
                 
                 let pow_absol = absol.powf(a);
@@ -386,4 +354,31 @@ pub fn calc_h_matrix(
             }
         }
     }
-}
\ No newline at end of file
+}
+pub fn check_slip_activation(
+    event_queue: &mut EventQueueComponent,
+    slip_status_map: &mut HashMap<CrystalEntity, SlipStatusComponent>,
+    gamma_rate_map: &HashMap<CrystalEntity, GammaRateComponent>,
+    time: f64,
+){
+    for entity in sorted_entities(gamma_rate_map){
+        if let Some(slip_status_component) = slip_status_map.get_mut(entity){
+            let gamma_rate_component = &gamma_rate_map[entity];
+            for index in 0..24{
+                let gamma_rate = gamma_rate_component.get_values(index).expect("Ошибка извлечения gamma_rate");
+                let was_active = slip_status_component.get_values(index).expect("Ошибка извлечения статуса системы скольжения");
+                //Событие публикуется один раз, при первом включении системы скольжения в зерне
+                if gamma_rate > 0.0 && !was_active {
+                    event_queue.push_event(GrainEvent::SlipSystemActivated {
+                        entity: entity.clone(),
+                        system: index,
+                        time,
+                    });
+                    slip_status_component.set_values(index, true);
+                }
+            }
+        } else {
+            panic!("Ошибка поиска статуса систем скольжения")
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//Модуль Python для сценарных расчетов (параметрические исследования, построение графиков).
+//Сборка: maturin develop --release (см. pyproject.toml).
+//Все массивы по зернам упорядочены по номеру сущности, см. Simulation.entity_ids().
+
+use std::fs;
+
+use nalgebra::Matrix3;
+use numpy::{ndarray::Array, IntoPyArray, PyArray1, PyArray2, PyArray3, PyReadonlyArray2};
+use pyo3::{
+    exceptions::{PyIOError, PyKeyError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyDict, PyFloat, PyInt, PyString},
+};
+use serde_json::Value;
+
+use crate::{
+    mmuvp::{
+        elasticity::systems::{calc_mean_eps, calc_mean_sigma},
+        params::{
+            components::Params,
+            systems::{from_json_str, get_value, set_value},
+        },
+    },
+    simulation::{Simulation, SimulationBuilder},
+};
+
+//Значение Python в JSON: числа, строки с единицами измерения ("400 um"), логические значения,
+//None, списки и словари. Проверка по схеме параметров выполняется в set_value
+fn json_from_python(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if value.is_none() {
+        return Ok(Value::Null);
+    }
+    if value.is_instance_of::<PyBool>() {
+        return Ok(Value::Bool(value.extract()?));
+    }
+    if value.is_instance_of::<PyString>() {
+        return Ok(Value::String(value.extract()?));
+    }
+    if let Ok(dict) = value.cast::<PyDict>() {
+        let mut map = serde_json::Map::new();
+        for (key, item) in dict.iter() {
+            map.insert(key.extract()?, json_from_python(&item)?);
+        }
+        return Ok(Value::Object(map));
+    }
+    if value.is_instance_of::<PyInt>() || (!value.is_instance_of::<PyFloat>() && value.extract::<i64>().is_ok()) {
+        return Ok(Value::from(value.extract::<i64>()?));
+    }
+    if let Ok(number) = value.extract::<f64>() {
+        return serde_json::Number::from_f64(number)
+            .map(Value::Number)
+            .ok_or_else(|| PyValueError::new_err("значение параметра должно быть конечным"));
+    }
+    match value.try_iter() {
+        Ok(items) => Ok(Value::Array(items.map(|item| json_from_python(&item?)).collect::<PyResult<Vec<Value>>>()?)),
+        Err(_) => Err(PyTypeError::new_err(format!("неподдерживаемый тип значения параметра: {}", value.get_type()))),
+    }
+}
+
+#[pyclass(name = "Params")]
+#[derive(Clone)]
+pub struct PyParams {
+    params: Params,
+}
+
+#[pymethods]
+impl PyParams {
+    //Params(path) читает параметры из файла param.json, Params() создает набор по умолчанию
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(path: Option<&str>) -> PyResult<Self> {
+        let params = match path {
+            Some(path) => {
+                let json = fs::read_to_string(path).map_err(|err| PyIOError::new_err(err.to_string()))?;
+                from_json_str(&json).map_err(|err| PyValueError::new_err(err.to_string()))?
+            }
+            None => Params::new(),
+        };
+        Ok(PyParams { params })
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let params = from_json_str(json).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(PyParams { params })
+    }
+
+    //Доступ по составному ключу, например params["slip.tau_c"]
+    fn __getitem__(&self, key: &str) -> PyResult<f64> {
+        get_value(&self.params, key)
+            .as_ref()
+            .and_then(Value::as_f64)
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+    }
+
+    //Значение задается так же, как в param.json: params["polycrystal.gr_size"] = "400 um",
+    //params["recrystallization.grain_growth"] = False, params["recrystallization.stored_energy"] = "grain"
+    fn __setitem__(&mut self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let value = json_from_python(value)?;
+        //Целочисленные параметры (steps_num, grain_num и т.п.) сохраняются как целые
+        //Незаданный seed (null) также является целым параметром
+        let is_integer = get_value(&self.params, key).is_some_and(|value| value.is_u64() || value.is_i64() || value.is_null());
+        let value = match value.as_f64() {
+            Some(number) if is_integer && value.is_f64() && number.fract() == 0.0 => Value::from(number as i64),
+            _ => value,
+        };
+        set_value(&mut self.params, key, value).map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.params).expect("Ошибка сериализации параметров")
+    }
+
+}
+
+fn matrix_from_numpy(grad_v: PyReadonlyArray2<f64>) -> PyResult<Matrix3<f64>> {
+    let array = grad_v.as_array();
+    if array.shape() != [3, 3] {
+        return Err(PyValueError::new_err("градиент скорости должен быть массивом 3x3"));
+    }
+    Ok(Matrix3::from_fn(|i, j| array[[i, j]]))
+}
+
+fn matrix_to_numpy<'py>(py: Python<'py>, matrix: &Matrix3<f64>) -> Bound<'py, PyArray2<f64>> {
+    Array::from_shape_fn((3, 3), |(i, j)| matrix[(i, j)]).into_pyarray(py)
+}
+
+#[pyclass(name = "Simulation")]
+pub struct PySimulation {
+    simulation: Simulation,
+}
+
+#[pymethods]
+impl PySimulation {
+    //Simulation(params, grad_v=None): поликристалл с заданным градиентом скорости (по умолчанию растяжение)
+    #[new]
+    #[pyo3(signature = (params, grad_v=None))]
+    fn new(params: &PyParams, grad_v: Option<PyReadonlyArray2<f64>>) -> PyResult<Self> {
+        let mut builder = SimulationBuilder::new(params.params.clone());
+        if let Some(grad_v) = grad_v {
+            builder = builder.grad_v(matrix_from_numpy(grad_v)?);
+        }
+        Ok(PySimulation {
+            simulation: builder.build(),
+        })
+    }
+
+    //Шаги расчета по расписанию стадий (как в исполняемом файле), но без вывода в файлы
+    #[pyo3(signature = (steps=1))]
+    fn step(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.simulation.advance_stage();
+        }
+    }
+
+    //Один шаг с произвольным градиентом скорости и шагом по времени
+    fn advance(&mut self, grad_v: PyReadonlyArray2<f64>, dt: f64) -> PyResult<()> {
+        if !(dt > 0.0 && dt.is_finite()) {
+            return Err(PyValueError::new_err(format!("шаг по времени должен быть > 0, получено {}", dt)));
+        }
+        let grad_v = matrix_from_numpy(grad_v)?;
+        self.simulation.advance(grad_v, dt);
+        Ok(())
+    }
+
+    //Полный расчет с выводом в файлы, как в исполняемом файле
+    fn run(&mut self) {
+        self.simulation.run();
+    }
+
+    #[getter]
+    fn step_count(&self) -> i64 {
+        self.simulation.step
+    }
+
+    fn grain_count(&self) -> usize {
+        self.simulation.grain_count()
+    }
+
+    fn entity_ids<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u32>> {
+        let ids: Vec<u32> = self.simulation.get_entities().iter().map(|entity| entity.get_id()).collect();
+        PyArray1::from_vec(py, ids)
+    }
+
+    fn mean_stress<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let sigma = calc_mean_sigma(&self.simulation.sigma_map, &self.simulation.rotation_map, &self.simulation.gr_size_map);
+        matrix_to_numpy(py, &sigma)
+    }
+
+    fn mean_strain<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let eps = calc_mean_eps(&self.simulation.eps_map, &self.simulation.rotation_map, &self.simulation.gr_size_map);
+        matrix_to_numpy(py, &eps)
+    }
+
+    //Тензоры ориентации зерен, массив (N, 3, 3)
+    fn orientations<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<f64>> {
+        let entities = self.simulation.get_entities();
+        Array::from_shape_fn((entities.len(), 3, 3), |(n, i, j)| {
+            self.simulation.rotation_map[&entities[n]].get_tensor()[(i, j)]
+        })
+        .into_pyarray(py)
+    }
+
+    //Напряжения в зернах в лабораторной системе координат, массив (N, 3, 3), МПа
+    fn stresses<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<f64>> {
+        let entities = self.simulation.get_entities();
+        let tensors: Vec<Matrix3<f64>> = entities
+            .iter()
+            .map(|entity| {
+                let o = self.simulation.rotation_map[entity].get_tensor();
+                o * self.simulation.sigma_map[entity].get_tensor() * o.transpose()
+            })
+            .collect();
+        Array::from_shape_fn((entities.len(), 3, 3), |(n, i, j)| tensors[n][(i, j)]).into_pyarray(py)
+    }
+
+    fn grain_sizes<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        let sizes: Vec<f64> = self
+            .simulation
+            .get_entities()
+            .iter()
+            .map(|entity| self.simulation.gr_size_map[entity].get_value())
+            .collect();
+        PyArray1::from_vec(py, sizes)
+    }
+
+    fn recrystallized<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        let status: Vec<bool> = self
+            .simulation
+            .get_entities()
+            .iter()
+            .map(|entity| self.simulation.status_map[entity].get_value())
+            .collect();
+        PyArray1::from_vec(py, status)
+    }
+
+    //Критические напряжения систем скольжения, массив (N, 24), МПа
+    fn tau_c<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        let entities = self.simulation.get_entities();
+        Array::from_shape_fn((entities.len(), 24), |(n, k)| {
+            self.simulation.tau_c_map[&entities[n]].get_values(k).unwrap()
+        })
+        .into_pyarray(py)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "mmuvp")]
+fn mmuvp_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyParams>()?;
+    module.add_class::<PySimulation>()?;
+    Ok(())
+}
@@ -0,0 +1,753 @@
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use nalgebra::Matrix3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    base_fn::print_current_sys,
+    consts::{CHECKPOINT_FILE, FILE_OUTPUT_PATH, MANIFEST_FILE, SNAPSHOT_DIR},
+    manifest::{Manifest, RunHistory, SubsystemTimings},
+    snapshot::write_grain_snapshot,
+    insert_component,
+    remove_component,
+    mmuvp::{
+        elasticity::{components::*, systems::*},
+        entity::CrystalEntity,
+        events::{components::*, systems::*},
+        params::{
+            components::{Params, StageKind, StageParams, StoredEnergyMode},
+            systems::{get_stages, get_total_steps},
+        },
+        random::{components::RngComponent, systems::gen_seed},
+        recovery::{components::*, systems::*},
+        recrystallization::{components::*, systems::*},
+        rotation::{components::*, systems::*},
+        slide_system::{components::*, systems::*},
+        standart_deformation::uniaxial_tension,
+    },
+};
+
+//Поликристалл: HashMap всех компонентов по сущностям зерен и осредненные по поликристаллу величины.
+//Имена полей совпадают со стандартными именами переменных, используемыми в руководстве.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Simulation {
+    pub params: Params,
+    pub init_grad_v: Matrix3<f64>,
+    pub step: i64,
+    //Время и температура текущего шага
+    pub time: f64,
+    pub temp: f64,
+
+    pub rotation_map: HashMap<CrystalEntity, RotationComponent>,
+    pub grad_v_map: HashMap<CrystalEntity, GradVComponent>,
+    pub d_map: HashMap<CrystalEntity, DComponent>,
+    pub de_map: HashMap<CrystalEntity, DComponent>,
+    pub din_map: HashMap<CrystalEntity, DComponent>,
+    pub sigma_map: HashMap<CrystalEntity, SigmaComponent>,
+    pub sigma_rate_map: HashMap<CrystalEntity, SigmaRateComponent>,
+    pub elasticity_map: HashMap<CrystalEntity, ElasticityTensorComponent>,
+    pub eps_map: HashMap<CrystalEntity, EpsComponent>,
+    pub burgers_map: HashMap<CrystalEntity, BurgersVectorComponent>,
+    pub normals_map: HashMap<CrystalEntity, NormalVectorComponent>,
+    pub bn_map: HashMap<CrystalEntity, BNComponent>,
+    pub tau_map: HashMap<CrystalEntity, TauComponent>,
+    pub tau_c_map: HashMap<CrystalEntity, TauComponent>,
+    pub tau_rate_map: HashMap<CrystalEntity, TauRateComponent>,
+    pub tau_c_rate_map: HashMap<CrystalEntity, TauRateComponent>,
+    //Деформационное упрочнение систем скольжения (без слагаемого Холла-Петча)
+    pub tau_hard_map: HashMap<CrystalEntity, TauComponent>,
+    pub gamma_map: HashMap<CrystalEntity, GammaComponent>,
+    pub gamma_rate_map: HashMap<CrystalEntity, GammaRateComponent>,
+    pub slip_status_map: HashMap<CrystalEntity, SlipStatusComponent>,
+    pub h_vector_map: HashMap<CrystalEntity, HVectorComponent>,
+    pub h_matrix_map: HashMap<CrystalEntity, HMatrixComponent>,
+    pub gr_size_map: HashMap<CrystalEntity, GrainSizeComponent>,
+    pub est_map: HashMap<CrystalEntity, AccumEnergyComponent>,
+    pub est_rate_map: HashMap<CrystalEntity, AccumEnergyRateComponent>,
+    pub status_map: HashMap<CrystalEntity, StatusRecrystComponent>,
+    pub facet_mobility_map: HashMap<CrystalEntity, FacetMobilityComponent>,
+    pub subgrains_map: HashMap<CrystalEntity, SubGrainsComponent>,
+    pub df_recr_map: HashMap<CrystalEntity, DriveForceRecrComponent>,
+    pub df_recr_cryst_map: HashMap<CrystalEntity, DriveForceRecrCrystComponent>,
+    pub vel_facet_map: HashMap<CrystalEntity, VelocityFacetComponent>,
+    pub recovery_rate_map: HashMap<CrystalEntity, RecoveryRateComponent>,
+    pub rng_map: HashMap<CrystalEntity, RngComponent>,
+
+    pub polycrystal_sigma: SigmaComponent,
+    pub polycrystal_eps: EpsComponent,
+    pub polycrystal_din: DComponent,
+    pub polycrystal_de: DComponent,
+    pub est_poly_component: AccumEnergyComponent,
+    //Начальный объем поликристалла и наибольшее относительное отклонение от него за расчет
+    pub initial_volume: f64,
+    pub max_volume_error: f64,
+    pub recryst_history: RecrystHistoryComponent,
+    //Номер следующего зародыша: номера удаленных зерен повторно не используются
+    pub next_id: u32,
+    pub event_queue: EventQueueComponent,
+
+    //Папка вывода не входит в сохраняемое состояние расчета
+    #[serde(skip, default = "default_output_path")]
+    pub output_path: PathBuf,
+    #[serde(skip)]
+    pub quiet: bool,
+    #[serde(skip, default = "Instant::now")]
+    start_time: Instant,
+    #[serde(default)]
+    pub timings: SubsystemTimings,
+    #[serde(default)]
+    pub run_history: RunHistory,
+}
+
+fn default_output_path() -> PathBuf {
+    PathBuf::from(FILE_OUTPUT_PATH)
+}
+
+pub struct SimulationBuilder {
+    params: Params,
+    init_grad_v: Matrix3<f64>,
+    output_path: PathBuf,
+    quiet: bool,
+}
+
+impl SimulationBuilder {
+    pub fn new(params: Params) -> Self {
+        SimulationBuilder {
+            init_grad_v: uniaxial_tension(params.loading.strain_rate),
+            params,
+            output_path: default_output_path(),
+            quiet: false,
+        }
+    }
+
+    pub fn grad_v(mut self, grad_v: Matrix3<f64>) -> Self {
+        self.init_grad_v = grad_v;
+        self
+    }
+
+    pub fn output_path(mut self, path: &Path) -> Self {
+        self.output_path = path.to_path_buf();
+        self
+    }
+
+    //Без вывода текущего состояния на экран (например, при параллельных расчетах)
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn build(mut self) -> Simulation {
+        //Выбранный seed сохраняется в параметрах, чтобы расчет можно было повторить
+        self.params.seed = Some(self.params.seed.unwrap_or_else(gen_seed));
+        let mut simulation = Simulation {
+            params: self.params,
+            init_grad_v: self.init_grad_v,
+            step: 0,
+            time: 0.0,
+            temp: 0.0,
+            rotation_map: HashMap::new(),
+            grad_v_map: HashMap::new(),
+            d_map: HashMap::new(),
+            de_map: HashMap::new(),
+            din_map: HashMap::new(),
+            sigma_map: HashMap::new(),
+            sigma_rate_map: HashMap::new(),
+            elasticity_map: HashMap::new(),
+            eps_map: HashMap::new(),
+            burgers_map: HashMap::new(),
+            normals_map: HashMap::new(),
+            bn_map: HashMap::new(),
+            tau_map: HashMap::new(),
+            tau_c_map: HashMap::new(),
+            tau_rate_map: HashMap::new(),
+            tau_c_rate_map: HashMap::new(),
+            tau_hard_map: HashMap::new(),
+            gamma_map: HashMap::new(),
+            gamma_rate_map: HashMap::new(),
+            slip_status_map: HashMap::new(),
+            h_vector_map: HashMap::new(),
+            h_matrix_map: HashMap::new(),
+            gr_size_map: HashMap::new(),
+            est_map: HashMap::new(),
+            est_rate_map: HashMap::new(),
+            status_map: HashMap::new(),
+            facet_mobility_map: HashMap::new(),
+            subgrains_map: HashMap::new(),
+            df_recr_map: HashMap::new(),
+            df_recr_cryst_map: HashMap::new(),
+            vel_facet_map: HashMap::new(),
+            recovery_rate_map: HashMap::new(),
+            rng_map: HashMap::new(),
+            polycrystal_sigma: SigmaComponent::new(),
+            polycrystal_eps: EpsComponent::new(),
+            polycrystal_din: DComponent::new(),
+            polycrystal_de: DComponent::new(),
+            est_poly_component: AccumEnergyComponent::new(),
+            initial_volume: 0.0,
+            max_volume_error: 0.0,
+            recryst_history: RecrystHistoryComponent::new(),
+            next_id: 0,
+            event_queue: EventQueueComponent::new(),
+            output_path: self.output_path,
+            quiet: self.quiet,
+            start_time: Instant::now(),
+            timings: SubsystemTimings::new(),
+            run_history: RunHistory::default(),
+        };
+        simulation.temp = get_stages(&simulation.params)[0].temp;
+        simulation.initialize();
+        simulation
+    }
+}
+
+impl Simulation {
+    //Заполняет все HashMap новыми экземплярами компонентов для сущности
+    pub fn spawn_grain(&mut self, entity: &CrystalEntity) {
+        insert_component!(entity, RotationComponent::new(), self.rotation_map);
+        insert_component!(entity, GradVComponent::new(), self.grad_v_map);
+        insert_component!(entity, DComponent::new(), self.d_map);
+        insert_component!(entity, DComponent::new(), self.de_map);
+        insert_component!(entity, DComponent::new(), self.din_map);
+        insert_component!(entity, SigmaComponent::new(), self.sigma_map);
+        insert_component!(entity, SigmaRateComponent::new(), self.sigma_rate_map);
+        insert_component!(entity, ElasticityTensorComponent::new(), self.elasticity_map);
+        insert_component!(entity, EpsComponent::new(), self.eps_map);
+        insert_component!(entity, BurgersVectorComponent::new(), self.burgers_map);
+        insert_component!(entity, NormalVectorComponent::new(), self.normals_map);
+        insert_component!(entity, BNComponent::new(), self.bn_map);
+        insert_component!(entity, TauComponent::new(), self.tau_map);
+        insert_component!(entity, TauComponent::new(), self.tau_c_map);
+        insert_component!(entity, TauRateComponent::new(), self.tau_rate_map);
+        insert_component!(entity, TauRateComponent::new(), self.tau_c_rate_map);
+        insert_component!(entity, TauComponent::new(), self.tau_hard_map);
+        insert_component!(entity, GammaComponent::new(), self.gamma_map);
+        insert_component!(entity, GammaRateComponent::new(), self.gamma_rate_map);
+        insert_component!(entity, SlipStatusComponent::new(), self.slip_status_map);
+        insert_component!(entity, HVectorComponent::new(), self.h_vector_map);
+        insert_component!(entity, HMatrixComponent::new(), self.h_matrix_map);
+        insert_component!(entity, GrainSizeComponent::new(), self.gr_size_map);
+        insert_component!(entity, AccumEnergyComponent::new(), self.est_map);
+        insert_component!(entity, AccumEnergyRateComponent::new(), self.est_rate_map);
+        insert_component!(entity, StatusRecrystComponent::new(), self.status_map);
+        insert_component!(entity, FacetMobilityComponent::new(), self.facet_mobility_map);
+        insert_component!(entity, SubGrainsComponent::new(), self.subgrains_map);
+        insert_component!(entity, DriveForceRecrComponent::new(), self.df_recr_map);
+        insert_component!(entity, DriveForceRecrCrystComponent::new(), self.df_recr_cryst_map);
+        insert_component!(entity, VelocityFacetComponent::new(), self.vel_facet_map);
+        insert_component!(entity, RecoveryRateComponent::new(), self.recovery_rate_map);
+        insert_component!(entity, RngComponent::new(self.get_seed(), entity.get_id() as u64), self.rng_map);
+    }
+
+    fn initialize(&mut self) {
+        for i in 0..self.params.polycrystal.grain_num {
+            let entity = CrystalEntity::new(i.try_into().unwrap());
+            self.spawn_grain(&entity);
+        }
+        //Нумерация зародышей начинается с grain_num + 1, как и до введения счетчика
+        self.next_id = self.params.polycrystal.grain_num as u32 + 1;
+
+        let params = &self.params;
+        gen_uniform_distribution(&mut self.rotation_map, &mut self.rng_map);
+        initialize_burgers_vectors(&mut self.burgers_map);
+        initialize_normal_vectors(&mut self.normals_map);
+        initialize_bn(&mut self.bn_map, &self.burgers_map, &self.normals_map);
+        initialize_elasticity_tensor_fcc(&mut self.elasticity_map, params.elasticity.c11, params.elasticity.c12, params.elasticity.c44, params.elasticity.koef);
+        init_grain_size(&mut self.gr_size_map, params.polycrystal.gr_size, params.polycrystal.std_dev, &mut self.rng_map);
+        initialize_tau_c_hp(&mut self.tau_c_map, &self.gr_size_map, params.slip.tau_c, params.slip.b, params.slip.k_y);
+        initialize_grad_v(&mut self.grad_v_map, &self.rotation_map, self.init_grad_v);
+        initialize_d(&mut self.d_map, &self.grad_v_map);
+        initialize_subgrains(&mut self.subgrains_map, params.recrystallization.r0, params.recrystallization.num_sg, &mut self.rng_map);
+        initialize_drive_force_recr(&mut self.df_recr_map, &self.subgrains_map);
+        self.initial_volume = calc_volumes(&self.gr_size_map, &self.status_map).0;
+    }
+
+    //Один шаг по времени для всех зерен при заданном макроскопическом градиенте скорости.
+    //Возвращает все события шага; зародившиеся зерна уже добавлены в поликристалл.
+    pub fn advance(&mut self, grad_v: Matrix3<f64>, dt: f64) -> Vec<GrainEvent> {
+        let time = self.time;
+        let params = &self.params;
+        let mut timer = Instant::now();
+        initialize_grad_v(&mut self.grad_v_map, &self.rotation_map, grad_v);
+        initialize_d(&mut self.d_map, &self.grad_v_map);
+        calc_tau(&mut self.tau_map, &self.bn_map, &self.sigma_map);
+        calc_gamma_rate(&mut self.gamma_rate_map, &self.tau_map, &self.tau_c_map, params.slip.gamma_0, params.slip.m);
+        calc_gamma(&mut self.gamma_map, &self.gamma_rate_map, dt);
+        check_slip_activation(&mut self.event_queue, &mut self.slip_status_map, &self.gamma_rate_map, time);
+        self.timings.lap("slip", &mut timer);
+        calc_h_vector(&mut self.h_vector_map, &self.tau_c_map, params.hardening.tau_sat, params.hardening.h0, params.hardening.a);
+        calc_h_matrix(&mut self.h_matrix_map, &self.h_vector_map, params.hardening.qlat);
+        calc_tauc_rate_sat_law(&mut self.tau_c_rate_map, &self.h_matrix_map, &self.gamma_rate_map);
+        calc_tauc(&mut self.tau_c_map, &mut self.tau_c_rate_map, dt);
+        calc_recovery_rate(&mut self.recovery_rate_map, &self.gamma_rate_map, params.recovery.rate0, params.recovery.q, params.recrystallization.r, params.recovery.dynamic, self.temp);
+        calc_tauc_recovery(&mut self.tau_c_map, &mut self.tau_hard_map, &self.tau_c_rate_map, &self.recovery_rate_map, dt);
+        self.timings.lap("hardening", &mut timer);
+        calc_din(&mut self.din_map, &self.gamma_rate_map, &self.bn_map);
+        calc_de_elastic_plastic_deform(&mut self.de_map, &self.d_map, &self.din_map);
+        calc_hooke_law(&mut self.sigma_rate_map, &self.elasticity_map, &self.de_map);
+        calc_sigma(&mut self.sigma_map, &self.sigma_rate_map, dt);
+        calc_eps(&mut self.eps_map, &self.d_map, dt);
+        self.timings.lap("elasticity", &mut timer);
+        calc_accum_energy_rate(&mut self.est_rate_map, &self.sigma_map, &self.din_map, params.recrystallization.alfa);
+        calc_accum_energy(&mut self.est_map, &self.est_rate_map, dt);
+        calc_accum_energy_recovery(&mut self.est_map, &self.recovery_rate_map, dt);
+        calc_subgrain_coarsening(&mut self.subgrains_map, params.subgrains.egb, params.subgrains.m0, params.subgrains.q, params.recrystallization.r, self.temp, dt);
+        self.est_poly_component.set_value(calc_mean_accum_energy(&self.est_map, &self.gr_size_map));
+        let zener_pressure = self.calc_zener_pressure();
+        match params.recrystallization.stored_energy {
+            StoredEnergyMode::Mean => {
+                calc_drive_force_recr(&mut self.df_recr_map, &self.subgrains_map, &self.est_poly_component, params.recrystallization.egb, zener_pressure);
+                calc_drive_force_recr_cryst(&mut self.df_recr_cryst_map, &self.status_map, &self.gr_size_map, &self.est_poly_component, params.recrystallization.egb, zener_pressure);
+            }
+            StoredEnergyMode::Grain => {
+                calc_drive_force_recr_grain(&mut self.df_recr_map, &self.subgrains_map, &self.est_map, params.recrystallization.egb, zener_pressure);
+                calc_drive_force_recr_cryst_grain(&mut self.df_recr_cryst_map, &self.status_map, &self.gr_size_map, &self.est_map, &self.est_poly_component, params.recrystallization.egb, zener_pressure);
+            }
+        }
+        calc_facet_mobility(&mut self.facet_mobility_map, params.recrystallization.m0, params.recrystallization.q, params.recrystallization.r, self.temp);
+        calc_vel_facet(&mut self.vel_facet_map, &self.df_recr_cryst_map, &self.facet_mobility_map);
+        self.timings.lap("recrystallization", &mut timer);
+        if params.recrystallization.grain_growth {
+            calc_grain_growth(&mut self.gr_size_map, &mut self.tau_c_map, &self.vel_facet_map, &self.status_map, params.slip.b, params.slip.k_y, params.recrystallization.min_gr_size, dt);
+        }
+        check_new_grain(&mut self.event_queue, &self.df_recr_map, &mut self.gr_size_map, &mut self.subgrains_map, time);
+        check_consumed_grains(&mut self.event_queue, &self.gr_size_map, params.recrystallization.min_gr_size, time);
+
+        let events = self.event_queue.drain();
+        for event in events.iter() {
+            match event {
+                GrainEvent::GrainNucleated { parent, radius, .. } => self.spawn_nucleus(parent, *radius),
+                GrainEvent::GrainConsumed { entity, .. } => self.despawn_grain(entity),
+                GrainEvent::SlipSystemActivated { .. } => {}
+            }
+        }
+        self.check_volume_conservation();
+        self.timings.lap("nucleation", &mut timer);
+        self.step += 1;
+        self.time += dt;
+        events
+    }
+
+    //Удаляет компоненты зерна из всех HashMap
+    pub fn despawn_grain(&mut self, entity: &CrystalEntity) {
+        remove_component!(entity, self.rotation_map);
+        remove_component!(entity, self.grad_v_map);
+        remove_component!(entity, self.d_map);
+        remove_component!(entity, self.de_map);
+        remove_component!(entity, self.din_map);
+        remove_component!(entity, self.sigma_map);
+        remove_component!(entity, self.sigma_rate_map);
+        remove_component!(entity, self.elasticity_map);
+        remove_component!(entity, self.eps_map);
+        remove_component!(entity, self.burgers_map);
+        remove_component!(entity, self.normals_map);
+        remove_component!(entity, self.bn_map);
+        remove_component!(entity, self.tau_map);
+        remove_component!(entity, self.tau_c_map);
+        remove_component!(entity, self.tau_rate_map);
+        remove_component!(entity, self.tau_c_rate_map);
+        remove_component!(entity, self.tau_hard_map);
+        remove_component!(entity, self.gamma_map);
+        remove_component!(entity, self.gamma_rate_map);
+        remove_component!(entity, self.slip_status_map);
+        remove_component!(entity, self.h_vector_map);
+        remove_component!(entity, self.h_matrix_map);
+        remove_component!(entity, self.gr_size_map);
+        remove_component!(entity, self.est_map);
+        remove_component!(entity, self.est_rate_map);
+        remove_component!(entity, self.status_map);
+        remove_component!(entity, self.facet_mobility_map);
+        remove_component!(entity, self.subgrains_map);
+        remove_component!(entity, self.df_recr_map);
+        remove_component!(entity, self.df_recr_cryst_map);
+        remove_component!(entity, self.vel_facet_map);
+        remove_component!(entity, self.recovery_rate_map);
+        remove_component!(entity, self.rng_map);
+    }
+
+    //Контроль сохранения полного объема поликристалла. Предупреждение выводится один раз,
+    //когда отклонение впервые превышает допустимое
+    fn check_volume_conservation(&mut self) {
+        if self.initial_volume <= 0.0 {
+            return;
+        }
+        let (total_volume, _) = calc_volumes(&self.gr_size_map, &self.status_map);
+        let error = (total_volume / self.initial_volume - 1.0).abs();
+        let tolerance = self.params.recrystallization.volume_tolerance;
+        if error > tolerance && self.max_volume_error <= tolerance && !self.quiet {
+            println!(
+                "level=warn event=volume_not_conserved step={} volume_error={:.3e} tolerance={:.3e}",
+                self.step, error, tolerance
+            );
+        }
+        self.max_volume_error = self.max_volume_error.max(error);
+    }
+
+    fn spawn_nucleus(&mut self, parent: &CrystalEntity, radius: f64) {
+        let parent_orientation = self.rotation_map.get(parent).map(|rotation| rotation.get_tensor());
+        let entity = CrystalEntity::new(self.next_id);
+        self.next_id += 1;
+        self.spawn_grain(&entity);
+
+        let params = &self.params;
+        self.gr_size_map.get_mut(&entity).unwrap().set_value(radius);
+        self.status_map.get_mut(&entity).unwrap().set_value(true);
+        let rng = self.rng_map.get_mut(&entity).unwrap().get_rng();
+        let orientation = &params.recrystallization.nucleus_orientation;
+        let o = get_nucleus_orientation(parent_orientation.as_ref(), orientation.model, orientation.scatter, orientation.fraction, rng);
+        self.rotation_map.get_mut(&entity).unwrap().set_matrix(o).unwrap();
+        get_burgers_vectors(self.burgers_map.get_mut(&entity).unwrap());
+        get_normals_vector(self.normals_map.get_mut(&entity).unwrap());
+        get_new_bn(self.bn_map.get_mut(&entity).unwrap(), self.burgers_map.get(&entity).unwrap(), self.normals_map.get(&entity).unwrap());
+        get_elasticity_tensor_fcc(self.elasticity_map.get_mut(&entity).unwrap(), params.elasticity.c11, params.elasticity.c12, params.elasticity.c44, params.elasticity.koef);
+        //При миграции границ слагаемое Холла-Петча пересчитывается по размеру зерна, поэтому
+        //начальное значение для зародыша должно соответствовать его собственному размеру
+        let hp_size = if params.recrystallization.grain_growth { radius } else { params.polycrystal.gr_size };
+        reset_recrystallized_grain(
+            self.est_map.get_mut(&entity).unwrap(),
+            self.tau_c_map.get_mut(&entity).unwrap(),
+            self.tau_hard_map.get_mut(&entity).unwrap(),
+            params.slip.tau_c,
+            params.slip.b,
+            params.slip.k_y,
+            hp_size,
+        );
+        let rng = self.rng_map.get_mut(&entity).unwrap().get_rng();
+        get_subgrains(self.subgrains_map.get_mut(&entity).unwrap(), params.recrystallization.r0, params.recrystallization.num_sg, rng);
+    }
+
+    //Вычисление НДС для поликристалла, вывод интенсивностей в файл и вывод текущего состояния на экран
+    pub fn write_output(&mut self) {
+        self.write_time_series();
+        if !self.quiet {
+            print_current_sys(self.start_time.elapsed(), self.step, get_total_steps(&self.params), &self.polycrystal_eps, &self.polycrystal_sigma, self.grain_count());
+        }
+    }
+
+    //Строки всех файлов временных рядов для текущего момента времени
+    fn write_time_series(&mut self) {
+        self.calc_polycrystal_tensors();
+        write_intensity_to_file(&self.polycrystal_eps, &self.polycrystal_sigma, self.time, &self.output_path);
+        self.write_tensors();
+        print_mean_grainsize_to_file(&self.gr_size_map, self.time, &self.output_path);
+        write_volume_to_file(&self.gr_size_map, &self.status_map, self.initial_volume, self.time, &self.output_path);
+        self.write_recrystallized_fraction();
+        write_subgrain_histogram_to_file(&self.subgrains_map, self.params.output.subgrain_r_max, self.params.output.subgrain_bins, self.time, &self.output_path);
+    }
+
+    //Осреднение по поликристаллу напряжений, деформаций и скоростей неупругой и упругой деформации
+    pub fn calc_polycrystal_tensors(&mut self) {
+        self.polycrystal_sigma.set_tensor(calc_mean_sigma(&self.sigma_map, &self.rotation_map, &self.gr_size_map));
+        self.polycrystal_eps.set_tensor(calc_mean_eps(&self.eps_map, &self.rotation_map, &self.gr_size_map));
+        self.polycrystal_din.set_tensor(calc_mean_d(&self.din_map, &self.rotation_map, &self.gr_size_map));
+        self.polycrystal_de.set_tensor(calc_mean_d(&self.de_map, &self.rotation_map, &self.gr_size_map));
+    }
+
+    //Давление закрепления границ частицами в текущий момент времени
+    pub fn calc_zener_pressure(&self) -> f64 {
+        let pinning = &self.params.pinning;
+        let fraction = calc_particle_fraction(pinning.fraction, pinning.initial_fraction, pinning.precipitation_time, pinning.precipitation_n, self.time);
+        calc_zener_pressure(fraction, self.params.recrystallization.egb, pinning.radius)
+    }
+
+    //Доля рекристаллизованных зерен на шаге вывода: запись в recryst.dat и в историю для аппроксимации JMAK
+    fn write_recrystallized_fraction(&mut self) {
+        write_recrystallized_fraction_to_file(&self.gr_size_map, &self.status_map, self.calc_zener_pressure(), self.time, &self.output_path);
+        self.recryst_history.push_value(self.time, calc_recrystallized_fraction(&self.gr_size_map, &self.status_map));
+    }
+
+    fn write_tensors(&self) {
+        write_tensors_to_file(
+            &self.polycrystal_sigma.get_tensor(),
+            &self.polycrystal_eps.get_tensor(),
+            &self.polycrystal_din.get_tensor(),
+            &self.polycrystal_de.get_tensor(),
+            self.time,
+            &self.output_path,
+        );
+    }
+
+    //Стадия процесса, к которой относится шаг, номер ее первого шага и время ее начала.
+    //После последнего шага возвращается последняя стадия
+    pub fn get_stage(&self, step: i64) -> (usize, StageParams, i64, f64) {
+        let stages = get_stages(&self.params);
+        let mut start_step = 0;
+        let mut start_time = 0.0;
+        for (index, stage) in stages.iter().enumerate() {
+            let steps = stage.get_steps();
+            if step < start_step + steps || index == stages.len() - 1 {
+                return (index, stage.clone(), start_step, start_time);
+            }
+            start_step += steps;
+            start_time += stage.dt * steps as f64;
+        }
+        unreachable!("Список стадий не может быть пустым")
+    }
+
+    //Время шага по расписанию стадий (без накопления ошибки округления)
+    pub fn get_stage_time(&self, step: i64) -> f64 {
+        let (_, stage, start_step, start_time) = self.get_stage(step);
+        start_time + stage.dt * (step - start_step) as f64
+    }
+
+    //Градиент скорости стадии: растяжение с заданной скоростью или нулевой при выдержке.
+    //Без заданных стадий используется градиент скорости из SimulationBuilder
+    fn get_stage_grad_v(&self, stage: &StageParams) -> Matrix3<f64> {
+        match stage.kind {
+            StageKind::Deformation if self.params.stages.is_empty() => self.init_grad_v,
+            StageKind::Deformation => uniaxial_tension(stage.strain_rate),
+            StageKind::Anneal => Matrix3::zeros(),
+        }
+    }
+
+    //Начало стадии: сообщение в журнал и, при выдержке без релаксации, снятие напряжений
+    fn start_stage(&mut self, index: usize, stage: &StageParams) {
+        if !self.quiet {
+            println!(
+                "level=info event=stage_start stage={} name={} kind={:?} step={} time={} dt={:e} temp={}",
+                index, stage.name, stage.kind, self.step, self.time, stage.dt, stage.temp
+            );
+        }
+        if stage.kind == StageKind::Anneal && !stage.relax_stress {
+            for sigma_component in self.sigma_map.values_mut() {
+                sigma_component.set_tensor(Matrix3::zeros());
+            }
+        }
+    }
+
+    //Подготовка шага по расписанию стадий: время и температура шага, начало новой стадии
+    fn begin_stage_step(&mut self) -> StageParams {
+        let (index, stage, start_step, _) = self.get_stage(self.step);
+        self.time = self.get_stage_time(self.step);
+        self.temp = stage.temp;
+        if self.step == start_step && (index > 0 || !self.params.stages.is_empty()) {
+            self.start_stage(index, &stage);
+        }
+        stage
+    }
+
+    //Шаг расчета по расписанию стадий без вывода в файлы
+    pub fn advance_stage(&mut self) -> Vec<GrainEvent> {
+        let stage = self.begin_stage_step();
+        self.advance(self.get_stage_grad_v(&stage), stage.dt)
+    }
+
+    //Шаг расчета по расписанию стадий с выводом каждые write_step шагов
+    pub fn step(&mut self) {
+        let mut timer = Instant::now();
+        let stage = self.begin_stage_step();
+        if self.step % self.params.time.write_step == 0 {
+            self.write_output();
+        }
+        self.timings.lap("output", &mut timer);
+        self.write_snapshot_if_requested();
+        self.timings.lap("snapshots", &mut timer);
+        let events = self.advance(self.get_stage_grad_v(&stage), stage.dt);
+        timer = Instant::now();
+        write_events_to_file(&events, &self.output_path);
+        self.timings.lap("output", &mut timer);
+
+        let checkpoint_step = self.params.output.checkpoint_step;
+        if checkpoint_step > 0 && self.step % checkpoint_step == 0 {
+            self.run_history.lap();
+            self.save_checkpoint(&self.output_path.join(CHECKPOINT_FILE))
+                .expect("Ошибка записи контрольной точки");
+            self.timings.lap("checkpoints", &mut timer);
+        }
+    }
+
+    pub fn run(&mut self) {
+        self.run_history.begin(self.step);
+        let total_steps = get_total_steps(&self.params);
+        if !self.quiet {
+            println!(
+                "level=info event=start step={} last_step={} stages={} grains={} seed={} output={}",
+                self.step,
+                total_steps,
+                get_stages(&self.params).len(),
+                self.grain_count(),
+                self.get_seed(),
+                self.output_path.display()
+            );
+        }
+        //При продолжении с контрольной точки начальные полюсные фигуры уже записаны
+        if self.step == 0 {
+            write_pole_figure(&self.rotation_map, &self.output_path);
+        }
+        self.start_time = Instant::now();
+        while self.step < total_steps {
+            self.step();
+        }
+        self.time = self.get_stage_time(total_steps);
+        self.write_snapshot_if_requested();
+        //Вывод финального состояния поликристалла
+        self.write_time_series();
+        let jmak = fit_jmak(&self.recryst_history);
+        if !self.quiet {
+            print_current_sys(self.start_time.elapsed(), total_steps, total_steps, &self.polycrystal_eps, &self.polycrystal_sigma, self.grain_count());
+            match &jmak {
+                Some(fit) => println!("level=info event=jmak n={:.4} k={:.4e} r2={:.4} points={}", fit.n, fit.k, fit.r2, fit.points),
+                None => println!("level=info event=jmak status=not_enough_points"),
+            }
+        }
+        self.run_history.lap();
+        Manifest::new(self, jmak)
+            .write(&self.output_path)
+            .expect("Ошибка записи манифеста расчета");
+        if !self.quiet {
+            println!("level=info event=finish step={} grains={} manifest={}", self.step, self.grain_count(), self.output_path.join(MANIFEST_FILE).display());
+        }
+    }
+
+    //Снимок состояния всех зерен на шагах из output.snapshot_steps и распределение размеров
+    //зерен на шагах из output.grain_size_steps
+    fn write_snapshot_if_requested(&self) {
+        if self.params.output.snapshot_steps.contains(&self.step) {
+            write_grain_snapshot(self, &self.output_path).expect("Ошибка записи снимка состояния зерен");
+        }
+        if self.params.output.grain_size_steps.contains(&self.step) {
+            let snapshot_path = self.output_path.join(SNAPSHOT_DIR);
+            fs::create_dir_all(&snapshot_path).expect("Ошибка создания папки снимков");
+            write_grain_size_distribution(
+                &self.gr_size_map,
+                &self.status_map,
+                self.params.output.grain_size_bins,
+                self.step,
+                self.time,
+                &snapshot_path.join(format!("grain_size_{:06}.dat", self.step)),
+            )
+            .expect("Ошибка записи распределения размеров зерен");
+        }
+    }
+
+    //Контрольная точка: полное состояние расчета (компоненты всех зерен, включая зародившиеся,
+    //осредненные величины, номер шага, генераторы случайных чисел и параметры).
+    //Файл записывается через временный, чтобы прерванная запись не испортила предыдущую точку
+    pub fn save_checkpoint(&self, path: &Path) -> io::Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let mut buf_writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut buf_writer, self)?;
+        buf_writer.flush()?;
+        drop(buf_writer);
+        fs::rename(&tmp_path, path)
+    }
+
+    //Восстановление расчета. Папки ввода и вывода не сохраняются и задаются заново
+    pub fn load_checkpoint(path: &Path) -> io::Result<Simulation> {
+        let buf_reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(buf_reader)?)
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.params.seed.expect("Seed задается при создании расчета")
+    }
+
+    pub fn grain_count(&self) -> usize {
+        self.gr_size_map.len()
+    }
+
+    //Сущности всех зерен, упорядоченные по номеру
+    pub fn get_entities(&self) -> Vec<CrystalEntity> {
+        let mut entities: Vec<CrystalEntity> = self.gr_size_map.keys().cloned().collect();
+        entities.sort_by_key(|entity| entity.get_id());
+        entities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        manifest::format_utc,
+        mmuvp::{elasticity::systems::calc_mean_eps, params::systems::from_json_str},
+    };
+
+    //Растяжение 50 шагов по 1 мс и выдержка 10 шагов по 50 мс из input/param_anneal.json
+    fn anneal_params() -> Params {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("input").join("param_anneal.json");
+        let mut params = from_json_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        params.polycrystal.grain_num = 4;
+        params.recrystallization.num_sg = 20;
+        params.time.write_step = 20;
+        params.stages[0].duration = 0.05;
+        params.stages[1].duration = 0.5;
+        params.output.checkpoint_step = 0;
+        params.output.snapshot_steps.clear();
+        params.output.grain_size_steps.clear();
+        params
+    }
+
+    //Состояние зерен без величин, которые обновляются только при выводе в файлы, и без времени работы
+    fn state_value(simulation: &Simulation) -> serde_json::Value {
+        let mut value = serde_json::to_value(simulation).unwrap();
+        for key in ["polycrystal_sigma", "polycrystal_eps", "polycrystal_de", "recryst_history", "timings", "run_history"] {
+            value.as_object_mut().unwrap().remove(key);
+        }
+        value
+    }
+
+    #[test]
+    fn advance_stage_follows_the_stage_schedule_of_step() {
+        let output_path = std::env::temp_dir().join(format!("mmuvp_stages_{}", std::process::id()));
+        crate::base_fn::prepare_output_folder(&output_path).unwrap();
+        let mut with_output = SimulationBuilder::new(anneal_params()).output_path(&output_path).quiet(true).build();
+        let mut without_output = SimulationBuilder::new(anneal_params()).quiet(true).build();
+
+        let mean_eps = |simulation: &Simulation| calc_mean_eps(&simulation.eps_map, &simulation.rotation_map, &simulation.gr_size_map);
+        for _ in 0..50 {
+            with_output.step();
+            without_output.advance_stage();
+        }
+        assert_eq!(state_value(&with_output), state_value(&without_output));
+        let eps_after_tension = mean_eps(&without_output);
+        assert!(eps_after_tension[(0, 0)] > 0.0);
+
+        for _ in 0..10 {
+            with_output.step();
+            without_output.advance_stage();
+        }
+        assert_eq!(state_value(&with_output), state_value(&without_output));
+        //Выдержка: шаг 50 мс, деформация не меняется
+        assert!((without_output.time - 0.55).abs() < 1.0e-12);
+        assert_eq!(mean_eps(&without_output), eps_after_tension);
+
+        std::fs::remove_dir_all(output_path).unwrap();
+    }
+
+    #[test]
+    fn restart_keeps_run_start_and_accumulates_timings() {
+        let output_path = std::env::temp_dir().join(format!("mmuvp_restart_{}", std::process::id()));
+        crate::base_fn::prepare_output_folder(&output_path).unwrap();
+        let mut params = anneal_params();
+        params.output.checkpoint_step = 40;
+        let mut simulation = SimulationBuilder::new(params).output_path(&output_path).quiet(true).build();
+        simulation.run();
+
+        let mut restored = Simulation::load_checkpoint(&output_path.join(CHECKPOINT_FILE)).unwrap();
+        assert_eq!(restored.step, 40);
+        assert_eq!(restored.run_history.start_time, simulation.run_history.start_time);
+        let checkpoint_wall_time = restored.run_history.wall_time;
+        let checkpoint_slip = restored.timings.get_values()["slip"];
+        assert!(checkpoint_wall_time > 0.0 && checkpoint_slip > 0.0);
+
+        restored.output_path = output_path.clone();
+        restored.quiet = true;
+        restored.run();
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(output_path.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest["start_time"], format_utc(simulation.run_history.start_time.unwrap()).as_str());
+        assert_eq!(manifest["start_step"], 0);
+        assert_eq!(manifest["restarted_from_step"], 40);
+        assert!(manifest["wall_time"].as_f64().unwrap() > checkpoint_wall_time);
+        assert!(manifest["subsystem_time"]["slip"].as_f64().unwrap() > checkpoint_slip);
+
+        std::fs::remove_dir_all(output_path).unwrap();
+    }
+}
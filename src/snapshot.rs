@@ -0,0 +1,170 @@
+//Снимки состояния всех зерен на заданных шагах (output.snapshot_steps) для анализа распределений.
+//Каждый снимок записывается в папку snapshots в двух видах:
+//  grains_<шаг>.csv - таблица с заголовком, одна строка на зерно;
+//  grains_<шаг>.npy - структурированный массив NumPy с теми же полями (np.load(...)["tau_c"]).
+//Напряжения и деформации приводятся в лабораторной системе координат, компоненты в порядке 11, 22, 33, 12, 13, 23.
+#![allow(dead_code)]
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use nalgebra::Matrix3;
+
+use crate::{
+    consts::SNAPSHOT_DIR,
+    mmuvp::{entity::CrystalEntity, rotation::systems::get_euler_angles_bunge},
+    simulation::Simulation,
+};
+
+const VOIGT_INDEX: [(usize, usize); 6] = [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)];
+const VOIGT_NAMES: [&str; 6] = ["11", "22", "33", "12", "13", "23"];
+
+//Состояние одного зерна в снимке
+pub struct GrainRecord {
+    pub id: u32,
+    pub euler: (f64, f64, f64),
+    pub gr_size: f64,
+    pub recrystallized: bool,
+    pub sigma: [f64; 6],
+    pub eps: [f64; 6],
+    pub est: f64,
+    pub tau_c: [f64; 24],
+    pub gamma: [f64; 24],
+}
+
+fn to_voigt(tensor: &Matrix3<f64>) -> [f64; 6] {
+    VOIGT_INDEX.map(|(i, j)| tensor[(i, j)])
+}
+
+fn to_array(get: impl Fn(usize) -> Option<f64>) -> [f64; 24] {
+    std::array::from_fn(|index| get(index).expect("Ошибка извлечения значения системы скольжения"))
+}
+
+pub fn collect_grain_records(simulation: &Simulation) -> Vec<GrainRecord> {
+    simulation
+        .get_entities()
+        .iter()
+        .map(|entity: &CrystalEntity| {
+            let o = simulation.rotation_map[entity].get_tensor();
+            GrainRecord {
+                id: entity.get_id(),
+                euler: get_euler_angles_bunge(&o),
+                gr_size: simulation.gr_size_map[entity].get_value(),
+                recrystallized: simulation.status_map[entity].get_value(),
+                sigma: to_voigt(&(o * simulation.sigma_map[entity].get_tensor() * o.transpose())),
+                eps: to_voigt(&(o * simulation.eps_map[entity].get_tensor() * o.transpose())),
+                est: simulation.est_map[entity].get_value(),
+                tau_c: to_array(|index| simulation.tau_c_map[entity].get_values(index)),
+                gamma: to_array(|index| simulation.gamma_map[entity].get_values(index)),
+            }
+        })
+        .collect()
+}
+
+//Запись снимка текущего шага в папку output_path/snapshots
+pub fn write_grain_snapshot(simulation: &Simulation, output_path: &Path) -> io::Result<()> {
+    let snapshot_path = output_path.join(SNAPSHOT_DIR);
+    fs::create_dir_all(&snapshot_path)?;
+    let records = collect_grain_records(simulation);
+    let name = format!("grains_{:06}", simulation.step);
+    write_grains_csv(&records, &snapshot_path.join(format!("{}.csv", name)))?;
+    write_grains_npy(&records, &snapshot_path.join(format!("{}.npy", name)))
+}
+
+pub fn write_grains_csv(records: &[GrainRecord], path: &Path) -> io::Result<()> {
+    let mut buf_writer = BufWriter::new(File::create(path)?);
+
+    let mut header = vec!["id", "phi1", "Phi", "phi2", "gr_size", "recrystallized"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<String>>();
+    header.extend(VOIGT_NAMES.iter().map(|name| format!("sigma_{}", name)));
+    header.extend(VOIGT_NAMES.iter().map(|name| format!("eps_{}", name)));
+    header.push("est".to_string());
+    header.extend((0..24).map(|index| format!("tau_c_{}", index)));
+    header.extend((0..24).map(|index| format!("gamma_{}", index)));
+    writeln!(buf_writer, "{}", header.join(","))?;
+
+    for record in records.iter() {
+        write!(
+            buf_writer,
+            "{},{},{},{},{:e},{}",
+            record.id, record.euler.0, record.euler.1, record.euler.2, record.gr_size, record.recrystallized as u8
+        )?;
+        for value in record.sigma.iter().chain(record.eps.iter()) {
+            write!(buf_writer, ",{:e}", value)?;
+        }
+        write!(buf_writer, ",{:e}", record.est)?;
+        for value in record.tau_c.iter().chain(record.gamma.iter()) {
+            write!(buf_writer, ",{:e}", value)?;
+        }
+        writeln!(buf_writer)?;
+    }
+    buf_writer.flush()
+}
+
+//Заголовок файла .npy версии 1.0: описание типа, порядок и форма массива.
+//Длина заголовка вместе с магической строкой дополняется пробелами до кратной 64
+pub fn write_npy_header(writer: &mut impl Write, descr: &str, shape: &[usize]) -> io::Result<()> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        _ => format!("({})", shape.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': {}, 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    let total = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - total % 64) % 64));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())
+}
+
+pub fn write_grains_npy(records: &[GrainRecord], path: &Path) -> io::Result<()> {
+    let mut buf_writer = BufWriter::new(File::create(path)?);
+    let descr = "[('id', '<u4'), ('phi1', '<f8'), ('Phi', '<f8'), ('phi2', '<f8'), ('gr_size', '<f8'), \
+                 ('recrystallized', '|b1'), ('sigma', '<f8', (6,)), ('eps', '<f8', (6,)), ('est', '<f8'), \
+                 ('tau_c', '<f8', (24,)), ('gamma', '<f8', (24,))]";
+    write_npy_header(&mut buf_writer, descr, &[records.len()])?;
+
+    for record in records.iter() {
+        buf_writer.write_all(&record.id.to_le_bytes())?;
+        for value in [record.euler.0, record.euler.1, record.euler.2, record.gr_size] {
+            buf_writer.write_all(&value.to_le_bytes())?;
+        }
+        buf_writer.write_all(&[record.recrystallized as u8])?;
+        for value in record.sigma.iter().chain(record.eps.iter()) {
+            buf_writer.write_all(&value.to_le_bytes())?;
+        }
+        buf_writer.write_all(&record.est.to_le_bytes())?;
+        for value in record.tau_c.iter().chain(record.gamma.iter()) {
+            buf_writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    buf_writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy_header_is_padded_to_64_bytes() {
+        for (descr, shape) in [("'<f8'", vec![3]), ("'<f8'", vec![300, 6]), ("[('id', '<u4'), ('gr_size', '<f8')]", vec![12345])] {
+            let mut buffer = Vec::new();
+            write_npy_header(&mut buffer, descr, &shape).unwrap();
+            assert_eq!(buffer.len() % 64, 0);
+            assert_eq!(&buffer[..8], b"\x93NUMPY\x01\x00");
+            assert_eq!(u16::from_le_bytes([buffer[8], buffer[9]]) as usize, buffer.len() - 10);
+            assert_eq!(buffer.last(), Some(&b'\n'));
+        }
+
+        let mut buffer = Vec::new();
+        write_npy_header(&mut buffer, "'<f8'", &[3]).unwrap();
+        let header = std::str::from_utf8(&buffer[10..]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (3,), }"));
+    }
+}
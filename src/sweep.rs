@@ -0,0 +1,226 @@
+//Параметрические исследования: набор расчетов с разными значениями параметров.
+//Расчеты выполняются параллельно, каждый в своей папке case_XXX с итоговым файлом параметров,
+//сводная таблица результатов записывается в summary.dat. Ошибка или аварийное завершение одного расчета
+//не прерывает исследование: расчет отмечается в таблице как неудачный.
+//
+//Файл исследования (JSON) содержит либо сетку значений, либо список наборов параметров:
+//  {"grid": {"recrystallization.temp": ["723 K", "773 K"], "loading.strain_rate": [1e-3, 1e-2]}}
+//  {"cases": [{"recrystallization.temp": "723 K"}, {"polycrystal.gr_size": "200 um"}]}
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    base_fn::prepare_output_folder,
+    mmuvp::{
+        elasticity::systems::calc_intensity_s,
+        params::{
+            components::{ConfigError, Params},
+            systems::{from_value, get_value, insert_value},
+        },
+        recrystallization::systems::{calc_mean_grain_size, calc_recrystallized_fraction},
+    },
+    simulation::SimulationBuilder,
+};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SweepConfig {
+    #[serde(default)]
+    pub grid: Option<BTreeMap<String, Vec<Value>>>,
+    #[serde(default)]
+    pub cases: Option<Vec<BTreeMap<String, Value>>>,
+}
+
+//Один расчет исследования: имя папки, измененные параметры и итоговый набор параметров
+pub struct SweepCase {
+    pub name: String,
+    pub overrides: Vec<(String, Value)>,
+    pub params: Params,
+}
+
+//Основные результаты расчета для сводной таблицы
+pub struct CaseSummary {
+    pub name: String,
+    pub stress_intensity: f64,
+    pub mean_grain_size: f64,
+    pub recrystallized_fraction: f64,
+    pub grain_count: usize,
+}
+
+pub fn read_sweep(path: &Path) -> Result<SweepConfig, ConfigError> {
+    let json_string = fs::read_to_string(path)
+        .map_err(|err| ConfigError::new(vec![format!("Ошибка открытия файла {}: {}", path.display(), err)]))?;
+    serde_json::from_str(&json_string)
+        .map_err(|err| ConfigError::new(vec![format!("Ошибка чтения файла исследования {}: {}", path.display(), err)]))
+}
+
+impl SweepConfig {
+    //Наборы измененных параметров для всех расчетов
+    pub fn get_overrides(&self) -> Result<Vec<Vec<(String, Value)>>, ConfigError> {
+        match (&self.grid, &self.cases) {
+            (Some(grid), None) => {
+                //Декартово произведение значений всех параметров сетки
+                let mut sets: Vec<Vec<(String, Value)>> = vec![Vec::new()];
+                for (key, values) in grid.iter() {
+                    if values.is_empty() {
+                        return Err(ConfigError::new(vec![format!("пустой список значений \"{}\" в сетке", key)]));
+                    }
+                    sets = sets
+                        .iter()
+                        .flat_map(|set| {
+                            values.iter().map(move |value| {
+                                let mut set = set.clone();
+                                set.push((key.clone(), value.clone()));
+                                set
+                            })
+                        })
+                        .collect();
+                }
+                Ok(sets)
+            }
+            (None, Some(cases)) => Ok(cases
+                .iter()
+                .map(|case| case.iter().map(|(key, value)| (key.clone(), value.clone())).collect())
+                .collect()),
+            _ => Err(ConfigError::new(vec![
+                "файл исследования должен содержать либо \"grid\", либо \"cases\"".to_string(),
+            ])),
+        }
+    }
+
+    //Проверка всех расчетов до запуска: ошибки всех наборов параметров собираются в один отчет
+    pub fn resolve_cases(&self, base: &Params) -> Result<Vec<SweepCase>, ConfigError> {
+        let base_value = serde_json::to_value(base).expect("Ошибка сериализации параметров");
+        let mut cases = Vec::new();
+        let mut problems = Vec::new();
+        for (index, overrides) in self.get_overrides()?.into_iter().enumerate() {
+            let name = format!("case_{:03}", index);
+            let mut value = base_value.clone();
+            for (key, new_value) in overrides.iter() {
+                if let Err(err) = insert_value(&mut value, key, new_value.clone()) {
+                    problems.push(format!("{}: {}", name, err));
+                }
+            }
+            match from_value(&value) {
+                Ok(params) => cases.push(SweepCase { name, overrides, params }),
+                Err(err) => problems.extend(err.get_problems().iter().map(|problem| format!("{}: {}", name, problem))),
+            }
+        }
+        if problems.is_empty() {
+            Ok(cases)
+        } else {
+            Err(ConfigError::new(problems))
+        }
+    }
+}
+
+//Расчет одного набора параметров в папке output_root/<имя расчета>
+pub fn run_case(case: &SweepCase, output_root: &Path) -> Result<CaseSummary, String> {
+    let output_path: PathBuf = output_root.join(&case.name);
+    prepare_output_folder(&output_path).map_err(|err| format!("Ошибка подготовки папки расчета: {}", err))?;
+    let config = serde_json::to_string_pretty(&case.params).map_err(|err| format!("Ошибка сериализации параметров: {}", err))?;
+    fs::write(output_path.join("config.json"), config).map_err(|err| format!("Ошибка записи файла config.json: {}", err))?;
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut simulation = SimulationBuilder::new(case.params.clone())
+            .output_path(&output_path)
+            .quiet(true)
+            .build();
+        simulation.run();
+
+        CaseSummary {
+            name: case.name.clone(),
+            stress_intensity: calc_intensity_s(&simulation.polycrystal_sigma),
+            mean_grain_size: calc_mean_grain_size(&simulation.gr_size_map),
+            recrystallized_fraction: calc_recrystallized_fraction(&simulation.gr_size_map, &simulation.status_map),
+            grain_count: simulation.grain_count(),
+        }
+    }));
+    result.map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "аварийное завершение расчета".to_string());
+        //Сообщение записывается в одну ячейку таблицы
+        message.split_whitespace().collect::<Vec<&str>>().join(" ")
+    })
+}
+
+//Параллельный расчет всех наборов параметров. jobs - число потоков (по умолчанию все ядра)
+pub fn run_sweep(cases: &[SweepCase], output_root: &Path, jobs: Option<usize>) -> Vec<Result<CaseSummary, String>> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0))
+        .build()
+        .expect("Ошибка создания пула потоков");
+    let summaries: Vec<Result<CaseSummary, String>> = pool.install(|| {
+        cases
+            .par_iter()
+            .map(|case| {
+                let summary = run_case(case, output_root);
+                match &summary {
+                    Ok(summary) => println!(
+                        "level=info event=sweep_case case={} status=ok grains={} sigma_i={:.4e}",
+                        case.name, summary.grain_count, summary.stress_intensity
+                    ),
+                    Err(err) => println!("level=error event=sweep_case case={} status=failed error={:?}", case.name, err),
+                }
+                summary
+            })
+            .collect()
+    });
+    write_summary(cases, &summaries, output_root);
+    summaries
+}
+
+//Сводная таблица: имя расчета, значения измененных параметров (СИ), интенсивность напряжений (МПа),
+//средний размер зерна (м), объемная доля рекристаллизованных зерен, число зерен, состояние расчета
+//(ok или failed: <ошибка>; у неудачных расчетов результаты заменяются на "-")
+pub fn write_summary(cases: &[SweepCase], summaries: &[Result<CaseSummary, String>], output_root: &Path) {
+    let file = File::create(output_root.join("summary.dat")).expect("Ошибка открытия файла summary.dat");
+    let mut buf_writer = BufWriter::new(file);
+
+    let mut keys: Vec<&str> = Vec::new();
+    for case in cases.iter() {
+        for (key, _) in case.overrides.iter() {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+    }
+
+    write!(buf_writer, "case\t").expect("Ошибка записи заголовка в summary.dat");
+    for key in keys.iter() {
+        write!(buf_writer, "{}\t", key).expect("Ошибка записи заголовка в summary.dat");
+    }
+    writeln!(buf_writer, "sigma_i\tmean_gr_size\trecr_fraction\tgrain_count\tstatus").expect("Ошибка записи заголовка в summary.dat");
+
+    for (case, summary) in cases.iter().zip(summaries.iter()) {
+        write!(buf_writer, "{}\t", case.name).expect("Ошибка записи в summary.dat");
+        for key in keys.iter() {
+            let value = get_value(&case.params, key).map_or("-".to_string(), |value| value.to_string());
+            write!(buf_writer, "{}\t", value).expect("Ошибка записи в summary.dat");
+        }
+        match summary {
+            Ok(summary) => writeln!(
+                buf_writer,
+                "{:.4e}\t{:.4e}\t{:.4e}\t{}\tok",
+                summary.stress_intensity, summary.mean_grain_size, summary.recrystallized_fraction, summary.grain_count
+            ),
+            Err(err) => writeln!(buf_writer, "-\t-\t-\t-\tfailed: {}", err),
+        }
+        .expect("Ошибка записи в summary.dat");
+    }
+    buf_writer.flush().expect("Ошибка завершения записи в summary.dat");
+}
@@ -0,0 +1,33 @@
+//Общие вспомогательные функции интеграционных тестов.
+//Небольшой поликристалл для тестов; тот же построитель для тестов библиотеки - SmallParams в params/systems.rs
+#![allow(dead_code)]
+
+use mmuvp_ecs_2_0::mmuvp::params::{components::Params, systems::set_value};
+
+pub struct SmallParams {
+    params: Params,
+}
+
+impl SmallParams {
+    pub fn new(grain_num: usize, num_sg: usize) -> Self {
+        let mut params = Params::new();
+        set_value(&mut params, "polycrystal.grain_num", grain_num.into()).unwrap();
+        set_value(&mut params, "recrystallization.num_sg", num_sg.into()).unwrap();
+        SmallParams { params }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        set_value(&mut self.params, "seed", seed.into()).unwrap();
+        self
+    }
+
+    pub fn steps(mut self, steps_num: i64, write_step: i64) -> Self {
+        set_value(&mut self.params, "time.steps_num", steps_num.into()).unwrap();
+        set_value(&mut self.params, "time.write_step", write_step.into()).unwrap();
+        self
+    }
+
+    pub fn build(self) -> Params {
+        self.params
+    }
+}
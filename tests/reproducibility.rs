@@ -0,0 +1,56 @@
+//Два расчета с одинаковым seed должны давать одинаковые результаты
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use mmuvp_ecs_2_0::{
+    base_fn::prepare_output_folder,
+    mmuvp::params::components::Params,
+    simulation::SimulationBuilder,
+};
+
+use common::SmallParams;
+
+//4 с нагружения со скоростью 1e-2 1/с: за это время в зернах появляются зародыши
+fn small_params(seed: u64) -> Params {
+    SmallParams::new(8, 50).seed(seed).steps(4000, 250).build()
+}
+
+fn run(params: Params, name: &str) -> PathBuf {
+    let output_path = std::env::temp_dir().join(format!("mmuvp_reproducibility_{}_{}", std::process::id(), name));
+    prepare_output_folder(&output_path).unwrap();
+    let mut simulation = SimulationBuilder::new(params)
+        .output_path(&output_path)
+        .quiet(true)
+        .build();
+    simulation.run();
+    output_path
+}
+
+fn read(path: &Path, file: &str) -> String {
+    fs::read_to_string(path.join(file)).unwrap()
+}
+
+#[test]
+fn same_seed_gives_identical_output() {
+    let [first, second, other] = std::thread::scope(|scope| {
+        [(42, "first"), (42, "second"), (43, "other")]
+            .map(|(seed, name)| scope.spawn(move || run(small_params(seed), name)))
+            .map(|handle| handle.join().unwrap())
+    });
+
+    assert!(read(&first, "events.dat").contains("GrainNucleated"));
+    for file in ["rvout.dat", "events.dat", "recryst.dat", "volume.dat"] {
+        let first_output = read(&first, file);
+        assert!(!first_output.is_empty(), "{} пуст", file);
+        assert_eq!(first_output, read(&second, file), "{} различается при одинаковом seed", file);
+    }
+    assert_ne!(read(&first, "rvout.dat"), read(&other, "rvout.dat"));
+
+    for path in [first, second, other] {
+        fs::remove_dir_all(path).unwrap();
+    }
+}
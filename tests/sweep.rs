@@ -0,0 +1,33 @@
+//Неудачный расчет исследования отмечается в summary.dat, остальные расчеты выполняются
+mod common;
+
+use std::fs;
+
+use mmuvp_ecs_2_0::sweep::{run_sweep, SweepConfig};
+
+use common::SmallParams;
+
+#[test]
+fn failed_case_is_recorded_in_summary() {
+    let output_root = std::env::temp_dir().join(format!("mmuvp_sweep_{}", std::process::id()));
+    let sweep: SweepConfig = serde_json::from_str(r#"{"grid": {"seed": [1, 2]}}"#).unwrap();
+    let cases = sweep.resolve_cases(&SmallParams::new(2, 10).steps(20, 10).build()).unwrap();
+    //Папка на месте файла результатов: первый расчет завершается аварийно при записи
+    fs::create_dir_all(output_root.join("case_000").join("rvout.dat")).unwrap();
+
+    let summaries = run_sweep(&cases, &output_root, Some(1));
+    assert!(summaries[0].is_err());
+    assert_eq!(summaries[1].as_ref().unwrap().grain_count, 2);
+
+    let summary = fs::read_to_string(output_root.join("summary.dat")).unwrap();
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].ends_with("\tstatus"));
+    assert!(lines[1].starts_with("case_000\t1\t-\t-\t-\t-\tfailed: "));
+    assert!(lines[2].starts_with("case_001\t2\t") && lines[2].ends_with("\tok"));
+    for line in lines.iter() {
+        assert_eq!(line.split('\t').count(), 7);
+    }
+
+    fs::remove_dir_all(output_root).unwrap();
+}